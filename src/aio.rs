@@ -0,0 +1,1373 @@
+//! Using the client from an async runtime (requires the `async` feature).
+//!
+//! An `EventStream` is a gateway connection which runs as a task on the tokio
+//! runtime, heartbeating, resuming and reconnecting as `Connection` does, so
+//! any number of shards can be connected without a thread each. It yields the
+//! same `model` types as the blocking client. With the `voice` feature, voice
+//! connections are controlled through `EventStream::voice`, although audio is
+//! still sent and received on a thread per connection.
+//!
+//! `AsyncDiscord` makes REST requests over its own non-blocking HTTP client,
+//! sharing the rate limits and retry policy of the `Discord` it wraps. It has
+//! methods for the message, channel and member endpoints; any other `Discord`
+//! method may be called with `AsyncDiscord::call`, which runs it on tokio's
+//! blocking thread pool.
+//!
+//! ```ignore
+//! let discord = AsyncDiscord::new(Discord::from_bot_token(&token)?)?;
+//! let (mut events, ready) = discord.connect().await?;
+//! while let Some(event) = events.next().await {
+//!     if let Event::MessageCreate(message) = event? {
+//!         if message.content == "!ping" {
+//!             discord.send_message(message.channel_id, "Pong!", "", false).await?;
+//!         }
+//!     }
+//! }
+//! ```
+
+#[cfg(feature = "voice")]
+use std::collections::HashMap;
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error as StdError;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::future;
+use futures_util::{FutureExt, Sink, TryFutureExt};
+use http_body_util::{BodyExt, Full};
+use hyper::header::{ContentType, Headers};
+use hyper::status::StatusCode;
+use hyper1::Uri;
+use hyper_tls::{native_tls, HttpsConnector};
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::task::{self, JoinHandle};
+use tokio::time::{self, Interval, MissedTickBehavior};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tower_service::Service;
+
+use builders::{EditChannel, EditMember, EmbedBuilder, SendMessage};
+use connection::{presence_message, request_members_message, ConnectionBuilder, GATEWAY_VERSION};
+use model::*;
+use proxy::Proxy;
+use ratelimit::RateLimits;
+use retry::RetryPolicy;
+#[cfg(feature = "voice")]
+use voice::VoiceConnection;
+use {decode_json, editable_fields, message_body, percent_encode, Discord, Error, FileUpload, GetMessages};
+use {Object, RequestContext, Result, State, USER_AGENT};
+
+type Ws = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type BoxFuture<T> = Pin<Box<dyn Future<Output = Result<T>> + Send>>;
+type IoFuture<T> = Pin<Box<dyn Future<Output = io::Result<T>> + Send>>;
+
+macro_rules! api {
+	($self_:ident, $url:expr) => {
+		format!(concat!("{}", $url), $self_.discord.config.api_base)
+	};
+	($self_:ident, $url:expr, $($rest:tt)*) => {
+		format!(concat!("{}", $url), $self_.discord.config.api_base, $($rest)*)
+	};
+}
+
+/// An async handle to a `Discord` client.
+///
+/// Cloning an `AsyncDiscord` is cheap, and all clones share the same
+/// connection pool and rate limits, which are also shared with the wrapped
+/// `Discord`. A custom `Transport` applies only to the blocking client.
+#[derive(Clone)]
+pub struct AsyncDiscord {
+	discord: Arc<Discord>,
+	client: Client<HttpsConnector<Connector>, Full<Bytes>>,
+}
+
+impl AsyncDiscord {
+	/// Wrap a blocking client for use from an async runtime.
+	pub fn new(discord: Discord) -> Result<AsyncDiscord> {
+		let tls = native_tls::TlsConnector::new().map_err(|_| Error::Other("Error initializing TlsConnector"))?;
+		let connector = Connector {
+			proxy: discord.config.proxy.clone(),
+		};
+		Ok(AsyncDiscord {
+			discord: Arc::new(discord),
+			client: Client::builder(TokioExecutor::new()).build(HttpsConnector::from((connector, tls.into()))),
+		})
+	}
+
+	/// Get the underlying blocking client.
+	///
+	/// Its methods must not be called directly from async code, which would
+	/// block the runtime's worker thread.
+	pub fn blocking(&self) -> &Discord {
+		&self.discord
+	}
+
+	/// Make REST calls using the blocking client on tokio's blocking thread
+	/// pool, resolving to their result.
+	///
+	/// This is for `Discord` methods which `AsyncDiscord` lacks, including the
+	/// builder methods: `discord.call(move |d| d.get_audit_log(server, |a| a))`.
+	/// Several calls may be made in one closure, in which case they are made
+	/// in order on the same thread. Must be called from within a tokio
+	/// runtime.
+	pub fn call<T, F>(&self, f: F) -> Blocking<T>
+	where
+		T: Send + 'static,
+		F: FnOnce(&Discord) -> Result<T> + Send + 'static,
+	{
+		let discord = self.discord.clone();
+		Blocking(task::spawn_blocking(move || f(&discord)))
+	}
+
+	/// Get a client which records the given reason in the audit log for each
+	/// action, as with `Discord::with_reason`.
+	pub fn with_reason(&self, reason: &str) -> AsyncDiscord {
+		AsyncDiscord {
+			discord: Arc::new(self.discord.with_reason(reason)),
+			client: self.client.clone(),
+		}
+	}
+
+	/// Get a client which retries failed requests according to the given
+	/// policy, as with `Discord::with_retry_policy`.
+	pub fn with_retry_policy(&self, retry_policy: RetryPolicy) -> AsyncDiscord {
+		AsyncDiscord {
+			discord: Arc::new(self.discord.with_retry_policy(retry_policy)),
+			client: self.client.clone(),
+		}
+	}
+
+	/// Establish a websocket connection, resolving to a stream of its events
+	/// and the `ReadyEvent` sent upon establishing it.
+	///
+	/// The connection runs as a task on the tokio runtime which polls the
+	/// returned future.
+	pub fn connect(&self) -> Connect {
+		self.connect_with(|_| {})
+	}
+
+	/// Establish a sharded websocket connection, as with
+	/// `Discord::connect_sharded`.
+	pub fn connect_sharded(&self, shard_id: u8, total_shards: u8) -> Connect {
+		self.connect_with(move |builder| {
+			builder.with_shard(shard_id, total_shards);
+		})
+	}
+
+	/// Establish a websocket connection configured by the given function,
+	/// for example to set its intents.
+	pub fn connect_with<F>(&self, f: F) -> Connect
+	where
+		F: FnOnce(&mut ConnectionBuilder) + Send + 'static,
+	{
+		let discord = self.clone();
+		Connect {
+			gateway: Some(Request(Box::pin(self.get_gateway_url().map_ok(move |url| {
+				let mut builder = ConnectionBuilder::new(url, &discord.discord.token, &discord.discord.config);
+				f(&mut builder);
+				let (url, identify) = (builder.base_url().to_owned(), builder.identify());
+				Gateway::new(url, identify, discord)
+			})))),
+			stream: None,
+		}
+	}
+
+	fn get_gateway_url(&self) -> Request<String> {
+		self.decode("get", api!(self, "/gateway"), None, |value| {
+			let mut value: BTreeMap<String, String> = ::serde_json::from_value(value)?;
+			match value.remove("url") {
+				Some(url) => Ok(url),
+				None => Err(Error::Protocol("Response missing \"url\" in Discord::get_gateway_url()")),
+			}
+		})
+	}
+
+	/// Create a channel.
+	pub fn create_channel(&self, server: ServerId, name: &str, kind: ChannelType) -> Request<Channel> {
+		let map = json! {{
+			"name": name,
+			"type": kind.num(),
+		}};
+		self.decode("post", api!(self, "/guilds/{}/channels", server), Some(map), Channel::decode)
+	}
+
+	/// Get the list of channels in a server.
+	pub fn get_server_channels(&self, server: ServerId) -> Request<Vec<PublicChannel>> {
+		self.decode("get", api!(self, "/guilds/{}/channels", server), None, |value| {
+			decode_array(value, PublicChannel::decode)
+		})
+	}
+
+	/// Get information about a channel.
+	pub fn get_channel(&self, channel: ChannelId) -> Request<Channel> {
+		self.decode("get", api!(self, "/channels/{}", channel), None, Channel::decode)
+	}
+
+	/// Edit a channel's details, as with `Discord::edit_channel`.
+	pub fn edit_channel<F>(&self, channel: ChannelId, f: F) -> Request<PublicChannel>
+	where
+		F: FnOnce(EditChannel) -> EditChannel + Send + 'static,
+	{
+		// As with the blocking client, every field must be sent
+		let discord = self.clone();
+		Request(Box::pin(self.get_channel(channel).and_then(move |current| {
+			let map = match editable_fields(current) {
+				Ok(map) => EditChannel::__apply(f, map),
+				Err(err) => return future::Either::Left(future::err(err)),
+			};
+			let url = api!(discord, "/channels/{}", channel);
+			future::Either::Right(discord.decode("patch", url, Some(Value::Object(map)), PublicChannel::decode))
+		})))
+	}
+
+	/// Delete a channel.
+	pub fn delete_channel(&self, channel: ChannelId) -> Request<Channel> {
+		self.decode("delete", api!(self, "/channels/{}", channel), None, Channel::decode)
+	}
+
+	/// Indicate typing on a channel for the next 5 seconds.
+	pub fn broadcast_typing(&self, channel: ChannelId) -> Request<()> {
+		self.empty("post", api!(self, "/channels/{}/typing", channel), None)
+	}
+
+	/// Get a single message by ID from a given channel.
+	pub fn get_message(&self, channel: ChannelId, message: MessageId) -> Request<Message> {
+		self.decode("get", api!(self, "/channels/{}/messages/{}", channel, message), None, from_value)
+	}
+
+	/// Get messages in the backlog for a given channel, as with
+	/// `Discord::get_messages`.
+	pub fn get_messages(&self, channel: ChannelId, what: GetMessages, limit: Option<u64>) -> Request<Vec<Message>> {
+		let limit = limit.unwrap_or(50);
+		let url = match what {
+			GetMessages::MostRecent => api!(self, "/channels/{}/messages?limit={}", channel, limit),
+			GetMessages::Before(id) => api!(self, "/channels/{}/messages?limit={}&before={}", channel, limit, id),
+			GetMessages::After(id) => api!(self, "/channels/{}/messages?limit={}&after={}", channel, limit, id),
+			GetMessages::Around(id) => api!(self, "/channels/{}/messages?limit={}&around={}", channel, limit, id),
+		};
+		self.decode("get", url, None, from_value)
+	}
+
+	/// Gets the pinned messages for a given channel.
+	pub fn get_pinned_messages(&self, channel: ChannelId) -> Request<Vec<Message>> {
+		self.decode("get", api!(self, "/channels/{}/pins", channel), None, from_value)
+	}
+
+	/// Pin the given message to the given channel.
+	pub fn pin_message(&self, channel: ChannelId, message: MessageId) -> Request<()> {
+		self.empty("put", api!(self, "/channels/{}/pins/{}", channel, message), None)
+	}
+
+	/// Removes the given message from being pinned to the given channel.
+	pub fn unpin_message(&self, channel: ChannelId, message: MessageId) -> Request<()> {
+		self.empty("delete", api!(self, "/channels/{}/pins/{}", channel, message), None)
+	}
+
+	/// Send a message to a given channel, as with `Discord::send_message_ex`.
+	pub fn send_message_ex<F: FnOnce(SendMessage) -> SendMessage>(&self, channel: ChannelId, f: F) -> Request<Message> {
+		self.send_message_with_files(channel, Vec::new(), f)
+	}
+
+	/// Send a message to a given channel.
+	///
+	/// The `nonce` will be returned in the result and also transmitted to other
+	/// clients. The empty string is a good default if you don't care.
+	pub fn send_message(&self, channel: ChannelId, text: &str, nonce: &str, tts: bool) -> Request<Message> {
+		self.send_message_ex(channel, |b| b.content(text).nonce(nonce).tts(tts))
+	}
+
+	/// Send a message with any number of files attached to a given channel,
+	/// as with `Discord::send_message_with_files`.
+	pub fn send_message_with_files<F: FnOnce(SendMessage) -> SendMessage>(
+		&self,
+		channel: ChannelId,
+		files: Vec<FileUpload>,
+		f: F,
+	) -> Request<Message> {
+		let url = api!(self, "/channels/{}/messages", channel);
+		self.message("post", url, SendMessage::__build(f), files)
+	}
+
+	/// Send some embedded rich content attached to a message on a given
+	/// channel. `text` may be empty.
+	pub fn send_embed<F: FnOnce(EmbedBuilder) -> EmbedBuilder>(&self, channel: ChannelId, text: &str, f: F) -> Request<Message> {
+		self.send_message_ex(channel, |b| b.content(text).embed(f))
+	}
+
+	/// Edit a previously posted message, as with `Discord::edit_message_ex`.
+	pub fn edit_message_ex<F: FnOnce(SendMessage) -> SendMessage>(
+		&self,
+		channel: ChannelId,
+		message: MessageId,
+		f: F,
+	) -> Request<Message> {
+		self.edit_message_with_files(channel, message, Vec::new(), f)
+	}
+
+	/// Edit a previously posted message's text.
+	pub fn edit_message(&self, channel: ChannelId, message: MessageId, text: &str) -> Request<Message> {
+		self.edit_message_ex(channel, message, |b| b.content(text))
+	}
+
+	/// Edit a previously posted message, uploading new files to it, as with
+	/// `Discord::edit_message_with_files`.
+	pub fn edit_message_with_files<F: FnOnce(SendMessage) -> SendMessage>(
+		&self,
+		channel: ChannelId,
+		message: MessageId,
+		files: Vec<FileUpload>,
+		f: F,
+	) -> Request<Message> {
+		let url = api!(self, "/channels/{}/messages/{}", channel, message);
+		self.message("patch", url, SendMessage::__build(f), files)
+	}
+
+	/// Delete a previously posted message.
+	pub fn delete_message(&self, channel: ChannelId, message: MessageId) -> Request<()> {
+		self.empty("delete", api!(self, "/channels/{}/messages/{}", channel, message), None)
+	}
+
+	/// Bulk deletes a list of `MessageId`s from a given channel, as with
+	/// `Discord::delete_messages`.
+	pub fn delete_messages(&self, channel: ChannelId, messages: &[MessageId]) -> Request<()> {
+		let mut ids: Vec<u64> = messages.iter().map(|m| m.0).collect();
+		ids.sort();
+		ids.dedup();
+		if ids.len() < 2 {
+			return Request::err(Error::Other("A minimum of 2 message ids must be supplied"));
+		} else if ids.len() > 100 {
+			return Request::err(Error::Other("A maximum of 100 message ids may be supplied"));
+		}
+		let map = json! {{ "messages": ids }};
+		self.empty("post", api!(self, "/channels/{}/messages/bulk_delete", channel), Some(map))
+	}
+
+	/// Retrieve a member object for a server given the member's user id.
+	pub fn get_member(&self, server: ServerId, user: UserId) -> Request<Member> {
+		self.decode("get", api!(self, "/guilds/{}/members/{}", server, user), None, from_value)
+	}
+
+	/// Gets a page of a specific server's members, ordered by user ID, as
+	/// with `Discord::get_server_members`.
+	pub fn get_server_members(&self, server: ServerId, limit: Option<u32>, after: Option<UserId>) -> Request<Vec<Member>> {
+		let limit = limit.unwrap_or(1);
+		let url = match after {
+			Some(after) => api!(self, "/guilds/{}/members?limit={}&after={}", server, limit, after),
+			None => api!(self, "/guilds/{}/members?limit={}", server, limit),
+		};
+		self.decode("get", url, None, from_value)
+	}
+
+	/// Edit member information, including roles, nickname, and voice state.
+	///
+	/// See the `EditMember` struct for the editable fields.
+	pub fn edit_member<F: FnOnce(EditMember) -> EditMember>(&self, server: ServerId, user: UserId, f: F) -> Request<()> {
+		let map = EditMember::__build(f);
+		self.empty("patch", api!(self, "/guilds/{}/members/{}", server, user), Some(Value::Object(map)))
+	}
+
+	/// Edit the list of roles assigned to a member of a server.
+	pub fn edit_member_roles(&self, server: ServerId, user: UserId, roles: &[RoleId]) -> Request<()> {
+		self.edit_member(server, user, |m| m.roles(roles))
+	}
+
+	/// Add a role to a member of a server.
+	pub fn add_member_role(&self, server: ServerId, user: UserId, role: RoleId) -> Request<()> {
+		self.empty("put", api!(self, "/guilds/{}/members/{}/roles/{}", server, user, role), None)
+	}
+
+	/// Remove a role for a member of a server.
+	pub fn remove_member_role(&self, server: ServerId, user: UserId, role: RoleId) -> Request<()> {
+		self.empty("delete", api!(self, "/guilds/{}/members/{}/roles/{}", server, user, role), None)
+	}
+
+	/// Kick a member from a server.
+	pub fn kick_member(&self, server: ServerId, user: UserId) -> Request<()> {
+		self.empty("delete", api!(self, "/guilds/{}/members/{}", server, user), None)
+	}
+
+	/// Get the ban list for the given server.
+	pub fn get_bans(&self, server: ServerId) -> Request<Vec<Ban>> {
+		self.decode("get", api!(self, "/guilds/{}/bans", server), None, from_value)
+	}
+
+	/// Ban a user from the server, optionally deleting their recent messages.
+	///
+	/// Zero may be passed for `delete_message_days` if no deletion is desired.
+	pub fn add_ban(&self, server: ServerId, user: UserId, delete_message_days: u32) -> Request<()> {
+		let url = api!(self, "/guilds/{}/bans/{}?delete_message_days={}", server, user, delete_message_days);
+		self.empty("put", url, None)
+	}
+
+	/// Unban a user from the server.
+	pub fn remove_ban(&self, server: ServerId, user: UserId) -> Request<()> {
+		self.empty("delete", api!(self, "/guilds/{}/bans/{}", server, user), None)
+	}
+
+	/// Make a request with a JSON body, if any, and decode the JSON response.
+	fn decode<T, F>(&self, method: &'static str, url: String, body: Option<Value>, decode: F) -> Request<T>
+	where
+		T: Send + 'static,
+		F: FnOnce(Value) -> Result<T> + Send + 'static,
+	{
+		decoded(self.request(method, url, body), decode)
+	}
+
+	/// Make a request which is expected to return 204 No Content, logging
+	/// the response if it does not.
+	fn empty(&self, method: &'static str, url: String, body: Option<Value>) -> Request<()> {
+		Request(Box::pin(self.request(method, url, body).map_ok(|response| {
+			if response.status != StatusCode::NoContent {
+				debug!("Expected 204 No Content, got {}", response.status);
+				for header in response.headers.iter() {
+					debug!("Header: {}", header);
+				}
+				debug!("Content: {}", String::from_utf8_lossy(&response.body));
+			}
+		})))
+	}
+
+	fn request(&self, method: &'static str, url: String, body: Option<Value>) -> BoxFuture<Response> {
+		match body.map(|body| ::serde_json::to_vec(&body)) {
+			Some(Ok(body)) => self.send(method, url, ContentType::json(), Bytes::from(body), 1),
+			Some(Err(err)) => Box::pin(future::err(err.into())),
+			None => self.send(method, url, ContentType::json(), Bytes::new(), 1),
+		}
+	}
+
+	/// Send or edit a message, which is sent as JSON if there are no files.
+	fn message(&self, method: &'static str, url: String, map: Object, files: Vec<FileUpload>) -> Request<Message> {
+		match message_body(&map, files) {
+			Ok((content_type, body)) => decoded(self.send(method, url, content_type, Bytes::from(body), 1), from_value),
+			Err(err) => Request::err(err),
+		}
+	}
+
+	/// Send a request, waiting beforehand if the rate limit for `url` is
+	/// known to be exhausted, and retrying it as allowed by the
+	/// `RetryPolicy`, as the blocking client does.
+	///
+	/// A request which was not sent because its pooled connection had gone
+	/// stale is resent by the HTTP client, whatever the policy.
+	fn send(&self, method: &'static str, url: String, content_type: ContentType, body: Bytes, attempt: u32) -> BoxFuture<Response> {
+		let mut builder = ::hyper1::Request::builder()
+			.method(&method.to_uppercase()[..])
+			.uri(&url[..])
+			.header("Content-Type", content_type.to_string())
+			.header("Authorization", &self.discord.token[..])
+			.header("User-Agent", USER_AGENT);
+		if let Some(ref reason) = self.discord.reason {
+			builder = builder.header("X-Audit-Log-Reason", percent_encode(reason));
+		}
+		let request = match builder.body(Full::new(body.clone())) {
+			Ok(request) => request,
+			Err(_) => return Box::pin(future::err(Error::Other("Invalid REST request"))),
+		};
+		let discord = self.clone();
+		let client = self.client.clone();
+		let received = RateLimits::wait(&self.discord.rate_limits, method, &url)
+			.then(move |()| client.request(request).map_err(io_error))
+			.and_then(|response| {
+				let (parts, body) = response.into_parts();
+				body.collect().map(move |body| {
+					let mut headers = Headers::new();
+					for (name, value) in parts.headers.iter() {
+						headers.append_raw(name.as_str().to_owned(), value.as_bytes().to_vec());
+					}
+					Ok(Response {
+						status: StatusCode::from_u16(parts.status.as_u16()),
+						headers: headers,
+						body: body.map_err(io_error)?.to_bytes(),
+					})
+				})
+			});
+		Box::pin(received.then(move |result| {
+			let retry = discord.retries(method, &url, attempt, &result);
+			match retry {
+				Some(delay) => {
+					let retry = move |()| discord.send(method, url, content_type, body, attempt + 1);
+					Box::pin(time::sleep(delay).then(retry)) as BoxFuture<Response>
+				}
+				None => Box::pin(future::ready(check_status(method, &url, result))),
+			}
+		}))
+	}
+
+	/// Update the rate limits with the outcome of a request, returning how
+	/// long to wait before retrying it, if it should be.
+	fn retries(&self, method: &str, url: &str, attempt: u32, result: &io::Result<Response>) -> Option<Duration> {
+		let policy = &self.discord.config.retry_policy;
+		let rate_limited = match *result {
+			Ok(ref response) => self.discord.rate_limits.update(method, url, response.status, &response.headers),
+			Err(_) => false,
+		};
+		if !policy.allows(attempt) {
+			None
+		} else if rate_limited {
+			// The next attempt waits until the rate limit allows it
+			if policy.retries_rate_limited() {
+				debug!("Retrying after having been ratelimited");
+				Some(Duration::from_secs(0))
+			} else {
+				None
+			}
+		} else if match *result {
+			Ok(ref response) => policy.retries_status(method, response.status),
+			Err(ref err) => policy.retries_io_error(method, err),
+		} {
+			let delay = policy.backoff(attempt);
+			debug!("Retrying in {:?} after a failed attempt", delay);
+			Some(delay)
+		} else {
+			None
+		}
+	}
+}
+
+/// A future resolving to the result of a call made by `AsyncDiscord::call`.
+pub struct Blocking<T>(JoinHandle<Result<T>>);
+
+impl<T> Future for Blocking<T> {
+	type Output = Result<T>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<T>> {
+		match Pin::new(&mut self.0).poll(cx) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(Ok(result)) => Poll::Ready(result),
+			Poll::Ready(Err(err)) => {
+				if err.is_panic() {
+					::std::panic::resume_unwind(err.into_panic());
+				}
+				Poll::Ready(Err(Error::Other("Blocking REST call was cancelled")))
+			}
+		}
+	}
+}
+
+/// A future resolving to the result of a REST request made by an
+/// `AsyncDiscord` method.
+///
+/// Dropping it cancels the request, unless it has already been sent.
+pub struct Request<T>(BoxFuture<T>);
+
+impl<T: Send + 'static> Request<T> {
+	fn err(err: Error) -> Request<T> {
+		Request(Box::pin(future::err(err)))
+	}
+}
+
+impl<T> Future for Request<T> {
+	type Output = Result<T>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<T>> {
+		self.0.as_mut().poll(cx)
+	}
+}
+
+/// A REST response, read in full.
+struct Response {
+	status: StatusCode,
+	headers: Headers,
+	body: Bytes,
+}
+
+/// Convert non-success statuses to errors, recording the request in the
+/// error, as the blocking client does.
+fn check_status(method: &str, url: &str, result: io::Result<Response>) -> Result<Response> {
+	let response = result?;
+	if !response.status.is_success() {
+		let context = RequestContext::new(method, url, &response.headers);
+		return Err(Error::from_body(response.status, &response.body[..], Some(context)));
+	}
+	Ok(response)
+}
+
+/// Describe a failed request as an I/O error, of the same kind as the one
+/// which caused it if any, for the `RetryPolicy` to judge.
+fn io_error<E: StdError + Send + Sync + 'static>(err: E) -> io::Error {
+	let mut kind = io::ErrorKind::Other;
+	{
+		let mut source: Option<&(dyn StdError + 'static)> = Some(&err);
+		while let Some(cause) = source {
+			if let Some(err) = cause.downcast_ref::<io::Error>() {
+				kind = err.kind();
+				break;
+			}
+			if let Some(err) = cause.downcast_ref::<::hyper1::Error>() {
+				if err.is_incomplete_message() {
+					kind = io::ErrorKind::UnexpectedEof;
+					break;
+				}
+			}
+			source = cause.source();
+		}
+	}
+	io::Error::new(kind, err)
+}
+
+/// Decode a response's JSON body once it arrives.
+fn decoded<T, F>(response: BoxFuture<Response>, decode: F) -> Request<T>
+where
+	T: Send + 'static,
+	F: FnOnce(Value) -> Result<T> + Send + 'static,
+{
+	Request(Box::pin(response.and_then(move |response| {
+		future::ready(::serde_json::from_slice(&response.body).map_err(Error::from).and_then(decode))
+	})))
+}
+
+fn from_value<T: DeserializeOwned>(value: Value) -> Result<T> {
+	::serde_json::from_value(value).map_err(From::from)
+}
+
+/// Opens the connections of the async REST client, through the proxy if any.
+#[derive(Clone)]
+struct Connector {
+	proxy: Option<Proxy>,
+}
+
+impl Service<Uri> for Connector {
+	type Response = TokioIo<TcpStream>;
+	type Error = io::Error;
+	type Future = IoFuture<TokioIo<TcpStream>>;
+
+	fn poll_ready(&mut self, _: &mut Context) -> Poll<io::Result<()>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn call(&mut self, uri: Uri) -> Self::Future {
+		let (host, port) = host_and_port(&uri);
+		Box::pin(connect_tcp(host, port, self.proxy.clone()).map_ok(TokioIo::new))
+	}
+}
+
+/// A future resolving to a new `EventStream` and its `ReadyEvent`, returned
+/// by `AsyncDiscord::connect`.
+pub struct Connect {
+	gateway: Option<Request<Gateway>>,
+	stream: Option<EventStream>,
+}
+
+impl Future for Connect {
+	type Output = Result<(EventStream, ReadyEvent)>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+		if let Some(mut gateway) = self.gateway.take() {
+			match Pin::new(&mut gateway).poll(cx) {
+				Poll::Pending => {
+					self.gateway = Some(gateway);
+					return Poll::Pending;
+				}
+				Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+				Poll::Ready(Ok(gateway)) => self.stream = Some(gateway.spawn()),
+			}
+		}
+		loop {
+			let next = match self.stream {
+				Some(ref mut stream) => Pin::new(stream).poll_next(cx),
+				None => panic!("Connect polled after completion"),
+			};
+			match next {
+				Poll::Pending => return Poll::Pending,
+				Poll::Ready(Some(Ok(Event::Ready(ready)))) => {
+					return Poll::Ready(Ok((self.stream.take().unwrap(), ready)))
+				}
+				Poll::Ready(Some(Ok(other))) => debug!("Unexpected event before Ready: {:?}", other),
+				Poll::Ready(Some(Err(err))) => return Poll::Ready(Err(err)),
+				Poll::Ready(None) => return Poll::Ready(Err(Error::Protocol("Gateway closed during handshake"))),
+			}
+		}
+	}
+}
+
+/// A `Stream` of the events received over an async gateway connection.
+///
+/// As with `Connection::recv_event`, the connection is resumed or
+/// reestablished when it drops, yielding an `Event::Ready` if it had to be
+/// reestablished. The stream ends after yielding an error it could not
+/// recover from, such as an invalid token, and dropping it closes the
+/// connection.
+pub struct EventStream {
+	events: mpsc::UnboundedReceiver<Result<Event>>,
+	commands: mpsc::UnboundedSender<Value>,
+	#[cfg(feature = "voice")]
+	voice_handles: HashMap<Option<ServerId>, VoiceConnection>,
+	#[cfg(feature = "voice")]
+	user_id: UserId,
+	#[cfg(feature = "voice")]
+	proxy: Option<Proxy>,
+}
+
+impl EventStream {
+	/// Change the game information that this client reports as playing.
+	pub fn set_game(&self, game: Option<Game>) {
+		self.set_presence(game, OnlineStatus::Online, false)
+	}
+
+	/// Set the client to be playing this game, with defaults used for any
+	/// extended information.
+	pub fn set_game_name(&self, name: String) {
+		self.set_presence(Some(Game::playing(name)), OnlineStatus::Online, false);
+	}
+
+	/// Sets the active presence of the client, including game and/or status
+	/// information.
+	///
+	/// `afk` will help Discord determine where to send notifications.
+	pub fn set_presence(&self, game: Option<Game>, status: OnlineStatus, afk: bool) {
+		let _ = self.commands.send(presence_message(game, status, afk));
+	}
+
+	/// Requests a download of all member information for large servers, as
+	/// with `Connection::download_all_members`.
+	pub fn download_all_members(&self, state: &mut State) {
+		if let Some(message) = request_members_message(state) {
+			let _ = self.commands.send(message);
+		}
+	}
+
+	/// Get a handle to the voice connection for a server, as with
+	/// `Connection::voice`.
+	///
+	/// The handle is kept up to date by the events passing through this
+	/// stream, so the stream must keep being polled while voice is in use.
+	#[cfg(feature = "voice")]
+	pub fn voice(&mut self, server_id: Option<ServerId>) -> &mut VoiceConnection {
+		let EventStream {
+			ref mut voice_handles,
+			user_id,
+			ref commands,
+			ref proxy,
+			..
+		} = *self;
+		voice_handles.entry(server_id).or_insert_with(|| {
+			let commands = commands.clone();
+			let main_ws = Box::new(move |message| {
+				let _ = commands.send(message);
+			});
+			VoiceConnection::with_main_ws(server_id, user_id, main_ws, proxy.clone())
+		})
+	}
+
+	/// Drop the voice connection for a server, forgetting all settings, as
+	/// with `Connection::drop_voice`.
+	#[cfg(feature = "voice")]
+	pub fn drop_voice(&mut self, server_id: Option<ServerId>) {
+		self.voice_handles.remove(&server_id);
+	}
+
+	#[cfg(feature = "voice")]
+	fn update_voice(&mut self, event: &Event) {
+		match *event {
+			Event::Ready(ref ready) => self.user_id = ready.user.id,
+			Event::VoiceStateUpdate(server_id, ref voice_state) => {
+				self.voice(server_id).__update_state(voice_state)
+			}
+			Event::VoiceServerUpdate {
+				server_id,
+				ref endpoint,
+				ref token,
+				..
+			} => self.voice(server_id).__update_server(endpoint, token),
+			_ => {}
+		}
+	}
+}
+
+impl Stream for EventStream {
+	type Item = Result<Event>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Result<Event>>> {
+		let next = self.events.poll_recv(cx);
+		#[cfg(feature = "voice")]
+		{
+			if let Poll::Ready(Some(Ok(ref event))) = next {
+				self.update_voice(event);
+			}
+		}
+		next
+	}
+}
+
+/// The task behind an `EventStream`.
+struct Gateway {
+	url: String,
+	identify: Value,
+	discord: AsyncDiscord,
+	ws: Option<Ws>,
+	/// Set while connecting, in which case `ws` is `None`
+	connecting: Option<BoxFuture<(String, Ws)>>,
+	failed_attempts: u32,
+	heartbeat: Option<Interval>,
+	outbox: VecDeque<WsMessage>,
+	closing: bool,
+	session_id: Option<String>,
+	last_sequence: u64,
+	/// Whether the first `Ready` has been received
+	ready: bool,
+	/// Whether an invalid session has been reidentified since the last `Ready`
+	reidentified: bool,
+	events: Option<mpsc::UnboundedSender<Result<Event>>>,
+	commands: Option<mpsc::UnboundedReceiver<Value>>,
+}
+
+impl Gateway {
+	fn new(url: String, identify: Value, discord: AsyncDiscord) -> Gateway {
+		Gateway {
+			url: url,
+			identify: identify,
+			discord: discord,
+			ws: None,
+			connecting: None,
+			failed_attempts: 0,
+			heartbeat: None,
+			outbox: VecDeque::new(),
+			closing: false,
+			session_id: None,
+			last_sequence: 0,
+			ready: false,
+			reidentified: false,
+			events: None,
+			commands: None,
+		}
+	}
+
+	/// Start connecting on the current runtime.
+	fn spawn(mut self) -> EventStream {
+		let (events_tx, events) = mpsc::unbounded_channel();
+		let (commands, commands_rx) = mpsc::unbounded_channel();
+		self.events = Some(events_tx);
+		self.commands = Some(commands_rx);
+		self.connecting = Some(self.open());
+		#[cfg(feature = "voice")]
+		let proxy = self.discord.discord.config.proxy.clone();
+		tokio::spawn(self);
+		EventStream {
+			events: events,
+			commands: commands,
+			#[cfg(feature = "voice")]
+			voice_handles: HashMap::new(),
+			// Filled in from the `Ready` which `Connect` waits for
+			#[cfg(feature = "voice")]
+			user_id: UserId(0),
+			#[cfg(feature = "voice")]
+			proxy: proxy,
+		}
+	}
+
+	/// Open a new websocket, after a delay if reconnecting, and fetching a
+	/// new gateway URL if the current one has failed twice.
+	fn open(&self) -> BoxFuture<(String, Ws)> {
+		let url: BoxFuture<String> = if self.failed_attempts >= 2 {
+			Box::pin(self.discord.get_gateway_url())
+		} else {
+			Box::pin(future::ok(self.url.clone()))
+		};
+		let proxy = self.discord.discord.config.proxy.clone();
+		let connect = url.and_then(move |url| connect_websocket(&url, proxy).map_ok(move |ws| (url, ws)));
+		if self.ready {
+			Box::pin(time::sleep(Duration::from_secs(1)).then(move |()| connect))
+		} else {
+			Box::pin(connect)
+		}
+	}
+
+	fn send(&mut self, message: Value) {
+		self.outbox.push_back(WsMessage::Text(message.to_string()));
+	}
+
+	fn poll_gateway(&mut self, cx: &mut Context) -> Poll<Result<()>> {
+		loop {
+			// The EventStream closes the connection when dropped
+			loop {
+				let command = match self.commands {
+					Some(ref mut commands) => commands.poll_recv(cx),
+					None => Poll::Ready(None),
+				};
+				match command {
+					Poll::Ready(Some(message)) => self.send(message),
+					Poll::Ready(None) => return self.poll_close(cx),
+					Poll::Pending => break,
+				}
+			}
+
+			if let Some(mut connecting) = self.connecting.take() {
+				match connecting.as_mut().poll(cx) {
+					Poll::Pending => {
+						self.connecting = Some(connecting);
+						return Poll::Pending;
+					}
+					Poll::Ready(Ok((url, ws))) => {
+						self.url = url;
+						self.ws = Some(ws);
+						self.failed_attempts = 0;
+						let first = match self.session_id {
+							Some(ref session_id) => json! {{
+								"op": 6,
+								"d": {
+									"seq": self.last_sequence,
+									"token": self.discord.discord.token,
+									"session_id": session_id,
+								}
+							}},
+							None => self.identify.clone(),
+						};
+						self.outbox.push_front(WsMessage::Text(first.to_string()));
+					}
+					Poll::Ready(Err(err)) => {
+						if !self.ready || self.failed_attempts >= 2 {
+							return Poll::Ready(Err(err));
+						}
+						debug!("Failed to reconnect: {:?}", err);
+						self.failed_attempts += 1;
+						self.connecting = Some(self.open());
+						continue;
+					}
+				}
+			}
+
+			let tick = match self.heartbeat {
+				Some(ref mut heartbeat) => heartbeat.poll_tick(cx).is_ready(),
+				None => false,
+			};
+			if tick {
+				let heartbeat = json! {{ "op": 1, "d": self.last_sequence }};
+				self.send(heartbeat);
+			}
+
+			match self.poll_socket(cx) {
+				Poll::Pending => return Poll::Pending,
+				Poll::Ready(Some(message)) => self.receive(message)?,
+				Poll::Ready(None) => self.disconnect(None, String::new())?,
+			}
+		}
+	}
+
+	/// Write any queued messages, then read the next message, or `None` if
+	/// the connection was lost.
+	fn poll_socket(&mut self, cx: &mut Context) -> Poll<Option<WsMessage>> {
+		if let Poll::Ready(Err(())) = self.poll_write(cx) {
+			return Poll::Ready(None);
+		}
+		let ws = self.ws.as_mut().expect("Gateway neither connected nor connecting");
+		match Pin::new(ws).poll_next(cx) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(Some(Ok(message))) => Poll::Ready(Some(message)),
+			Poll::Ready(Some(Err(err))) => {
+				warn!("Websocket error, reconnecting: {:?}", err);
+				Poll::Ready(None)
+			}
+			Poll::Ready(None) => Poll::Ready(None),
+		}
+	}
+
+	fn poll_write(&mut self, cx: &mut Context) -> Poll<::std::result::Result<(), ()>> {
+		let ws = match self.ws {
+			Some(ref mut ws) => ws,
+			None => return Poll::Ready(Ok(())),
+		};
+		while !self.outbox.is_empty() {
+			match Pin::new(&mut *ws).poll_ready(cx) {
+				Poll::Pending => return Poll::Pending,
+				Poll::Ready(Ok(())) => {
+					let message = self.outbox.pop_front().unwrap();
+					if let Err(err) = Pin::new(&mut *ws).start_send(message) {
+						warn!("Error sending gateway message: {:?}", err);
+						return Poll::Ready(Err(()));
+					}
+				}
+				Poll::Ready(Err(err)) => {
+					warn!("Error sending gateway message: {:?}", err);
+					return Poll::Ready(Err(()));
+				}
+			}
+		}
+		Pin::new(ws).poll_flush(cx).map_err(|err| warn!("Error sending gateway message: {:?}", err))
+	}
+
+	fn poll_close(&mut self, cx: &mut Context) -> Poll<Result<()>> {
+		if self.ws.is_none() {
+			return Poll::Ready(Ok(()));
+		}
+		if !self.closing {
+			self.closing = true;
+			self.outbox.push_back(WsMessage::Close(Some(CloseFrame {
+				code: CloseCode::Normal,
+				reason: "".into(),
+			})));
+		}
+		match self.poll_write(cx) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(_) => Poll::Ready(Ok(())),
+		}
+	}
+
+	fn receive(&mut self, message: WsMessage) -> Result<()> {
+		let event = match message {
+			WsMessage::Text(text) => decode_json(text.as_bytes(), false, GatewayEvent::decode),
+			WsMessage::Binary(data) => decode_json(&data, true, GatewayEvent::decode),
+			WsMessage::Close(frame) => {
+				return match frame {
+					Some(frame) => self.disconnect(Some(frame.code.into()), frame.reason.into_owned()),
+					None => self.disconnect(None, String::new()),
+				}
+			}
+			_ => return Ok(()),
+		};
+		match event {
+			Err(err) => self.emit(Err(err)),
+			Ok(GatewayEvent::Hello(interval)) => {
+				let period = Duration::from_millis(interval);
+				let mut heartbeat = time::interval_at(time::Instant::now() + period, period);
+				heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+				self.heartbeat = Some(heartbeat);
+			}
+			Ok(GatewayEvent::Dispatch(sequence, event)) => {
+				self.last_sequence = sequence;
+				if let Event::Ready(ref ready) = event {
+					if ready.version != GATEWAY_VERSION {
+						warn!("Got protocol version {} instead of {}", ready.version, GATEWAY_VERSION);
+					}
+					self.session_id = Some(ready.session_id.clone());
+					self.ready = true;
+					self.reidentified = false;
+				}
+				self.emit(Ok(event));
+			}
+			Ok(GatewayEvent::Heartbeat(sequence)) => {
+				debug!("Heartbeat received with seq {}", sequence);
+				self.send(json! {{ "op": 1, "d": sequence }});
+			}
+			Ok(GatewayEvent::HeartbeatAck) => {}
+			Ok(GatewayEvent::Reconnect) => return self.disconnect(None, String::new()),
+			Ok(GatewayEvent::InvalidateSession) => {
+				if !self.ready && self.reidentified {
+					return Err(Error::Protocol(
+						"Invalid session during handshake. \
+						Double-check your token or consider waiting 5 seconds between starting shards.",
+					));
+				}
+				debug!("Session invalidated, reidentifying");
+				self.session_id = None;
+				self.reidentified = true;
+				let identify = self.identify.clone();
+				self.send(identify);
+			}
+		}
+		Ok(())
+	}
+
+	/// Drop the websocket and start resuming or reconnecting, unless the
+	/// closure is one which reconnecting would not fix.
+	fn disconnect(&mut self, code: Option<u16>, reason: String) -> Result<()> {
+		self.ws = None;
+		self.heartbeat = None;
+		self.outbox.clear();
+		match code {
+			_ if !self.ready => return Err(Error::Closed(code, reason)),
+			// Authentication failed, or invalid shard or intents
+			Some(4004) | Some(4010..=4014) => return Err(Error::Closed(code, reason)),
+			Some(4006) | Some(4007) | Some(4009) => self.session_id = None,
+			_ => {}
+		}
+		debug!("Closure, reconnecting: {:?}: {}", code, reason);
+		self.connecting = Some(self.open());
+		Ok(())
+	}
+
+	fn emit(&self, event: Result<Event>) {
+		if let Some(ref events) = self.events {
+			let _ = events.send(event);
+		}
+	}
+}
+
+impl Future for Gateway {
+	type Output = ();
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+		match self.poll_gateway(cx) {
+			Poll::Pending => Poll::Pending,
+			Poll::Ready(Ok(())) => Poll::Ready(()),
+			Poll::Ready(Err(err)) => {
+				self.emit(Err(err));
+				Poll::Ready(())
+			}
+		}
+	}
+}
+
+/// Open a websocket to the given gateway URL, through the proxy if any.
+fn connect_websocket(base_url: &str, proxy: Option<Proxy>) -> BoxFuture<Ws> {
+	// The request needs a path, which Discord's gateway URLs lack
+	let authority = base_url.splitn(2, "://").nth(1).unwrap_or(base_url);
+	let slash = if authority.contains('/') { "" } else { "/" };
+	let url = format!("{}{}?v={}", base_url, slash, GATEWAY_VERSION);
+	let (host, port) = match url.parse() {
+		Ok(uri) => host_and_port(&uri),
+		Err(_) => return Box::pin(future::err(Error::Other("Invalid gateway URL"))),
+	};
+	Box::pin(connect_tcp(host, port, proxy).map_err(Error::from).and_then(move |stream| {
+		tokio_tungstenite::client_async_tls(url, stream)
+			.map_ok(|(ws, _)| ws)
+			.map_err(Error::from)
+	}))
+}
+
+/// The host and port to connect to for an HTTP or websocket URL.
+fn host_and_port(uri: &Uri) -> (String, u16) {
+	let host = uri.host().unwrap_or("").trim_start_matches('[').trim_end_matches(']');
+	let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+		Some("http") | Some("ws") => 80,
+		_ => 443,
+	});
+	(host.to_owned(), port)
+}
+
+/// Open a TCP connection, through the proxy if any.
+fn connect_tcp(host: String, port: u16, proxy: Option<Proxy>) -> IoFuture<TcpStream> {
+	match proxy {
+		// The tunnel is opened with blocking I/O before being handed to tokio
+		Some(proxy) => Box::pin(
+			task::spawn_blocking(move || {
+				let stream = proxy.tunnel(&host, port)?;
+				stream.set_nonblocking(true)?;
+				Ok(stream)
+			})
+			.map(|joined| joined.unwrap_or_else(|err| Err(err.into())))
+			.and_then(|stream| future::ready(TcpStream::from_std(stream))),
+		),
+		None => Box::pin(TcpStream::connect((host, port))),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use futures_util::StreamExt;
+	use std::net::{self, TcpListener};
+	use std::sync::mpsc as std_mpsc;
+	use std::thread;
+	use test_support::{mock_discord, mock_responses, mock_server, relay_proxy, MESSAGE};
+	use DiscordBuilder;
+	use tokio::runtime::{Builder, Runtime};
+	use tokio_tungstenite::tungstenite::{self, WebSocket};
+
+	fn runtime() -> Runtime {
+		Builder::new_current_thread().enable_all().build().unwrap()
+	}
+
+	#[test]
+	fn call_on_blocking_pool() {
+		let (base, requests) = mock_server(
+			"200 OK",
+			r#"{"id":"1","username":"bot","discriminator":"0001","avatar":null,"email":null,"verified":true,"bot":true,"mfa_enabled":false}"#,
+		);
+		let discord = AsyncDiscord::new(mock_discord(&base)).unwrap();
+		let runtime = runtime();
+		let _runtime = runtime.enter();
+		let user = runtime.block_on(discord.call(|d| d.get_current_user())).unwrap();
+		assert_eq!(user.id, UserId(1));
+		assert!(requests.recv().unwrap().starts_with("GET /api/users/@me HTTP/1.1\r\n"));
+	}
+
+	#[test]
+	fn rest_requests() {
+		let (base, requests) = mock_responses(vec![
+			("200 OK", MESSAGE.to_owned()),
+			("204 No Content", String::new()),
+			("503 Service Unavailable", String::new()),
+			("200 OK", MESSAGE.to_owned()),
+		]);
+		let discord = AsyncDiscord::new(mock_discord(&base)).unwrap();
+		let runtime = runtime();
+
+		let message = runtime.block_on(discord.send_message(ChannelId(3), "hi", "", false)).unwrap();
+		assert_eq!(message.id, MessageId(2));
+		let request = requests.recv().unwrap();
+		assert!(request.starts_with("POST /api/channels/3/messages HTTP/1.1\r\n"));
+		assert!(request.to_lowercase().contains("\r\nauthorization: bot token\r\n"));
+		assert!(request.ends_with(r#"{"content":"hi","nonce":"","tts":false}"#));
+
+		runtime.block_on(discord.with_reason("Spam").kick_member(ServerId(5), UserId(1))).unwrap();
+		let request = requests.recv().unwrap();
+		assert!(request.starts_with("DELETE /api/guilds/5/members/1 HTTP/1.1\r\n"));
+		assert!(request.to_lowercase().contains("\r\nx-audit-log-reason: spam\r\n"));
+
+		// Failures are retried under the client's policy
+		let policy = RetryPolicy::default().base_delay(Duration::from_millis(1));
+		let message = runtime
+			.block_on(discord.with_retry_policy(policy).get_message(ChannelId(3), MessageId(2)))
+			.unwrap();
+		assert_eq!(message.id, MessageId(2));
+		assert_eq!(requests.iter().count(), 2);
+	}
+
+	const READY: &'static str = r#"{"op":0,"s":1,"t":"READY","d":{"v":6,"session_id":"abc",
+		"user":{"id":"1","username":"bot","discriminator":"0001","avatar":null,"email":null,"verified":true,"bot":true,"mfa_enabled":false},
+		"private_channels":[],"presences":[],"relationships":[],"guilds":[],"_trace":[]}}"#;
+
+	/// Read messages until one with the given opcode.
+	fn expect_op(ws: &mut WebSocket<net::TcpStream>, op: u64) -> Value {
+		loop {
+			if let WsMessage::Text(text) = ws.read().unwrap() {
+				let value: Value = ::serde_json::from_str(&text).unwrap();
+				if value["op"] == op {
+					return value;
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn gateway_through_proxy() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let gateway = listener.local_addr().unwrap().to_string();
+		let body = format!(r#"{{"url":"ws://{}"}}"#, gateway);
+		let (base, _requests) = mock_server("200 OK", &body);
+		thread::spawn(move || {
+			let mut ws = tungstenite::accept(listener.accept().unwrap().0).unwrap();
+			ws.send(WsMessage::text(r#"{"op":10,"d":{"heartbeat_interval":45000}}"#)).unwrap();
+			expect_op(&mut ws, 2);
+			ws.send(WsMessage::text(READY)).unwrap();
+			let _ = ws.read();
+		});
+
+		let (addr, connects) = relay_proxy();
+		let discord = DiscordBuilder::new()
+			.with_api_base(&base)
+			.with_proxy(Proxy::parse(&format!("http://{}", addr)).unwrap())
+			.from_bot_token("token")
+			.unwrap();
+		let runtime = runtime();
+		let _runtime = runtime.enter();
+		let (_events, ready) = runtime.block_on(AsyncDiscord::new(discord).unwrap().connect()).unwrap();
+		assert_eq!(ready.session_id, "abc");
+		let api = base.trim_start_matches("http://").trim_end_matches("/api");
+		assert!(connects.recv().unwrap().starts_with(&format!("CONNECT {} ", api)));
+		assert!(connects.recv().unwrap().starts_with(&format!("CONNECT {} ", gateway)));
+	}
+
+	#[test]
+	fn event_stream() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let gateway = format!(r#"{{"url":"ws://{}"}}"#, listener.local_addr().unwrap());
+		let (base, _requests) = mock_server("200 OK", &gateway);
+		let (tx, received) = std_mpsc::channel();
+		thread::spawn(move || {
+			let hello = r#"{"op":10,"d":{"heartbeat_interval":50}}"#;
+			let mut ws = tungstenite::accept(listener.accept().unwrap().0).unwrap();
+			ws.send(WsMessage::text(hello)).unwrap();
+			tx.send(expect_op(&mut ws, 2)).unwrap();
+			ws.send(WsMessage::text(READY)).unwrap();
+			ws.send(WsMessage::text(r#"{"op":0,"s":2,"t":"MESSAGE_DELETE","d":{"channel_id":"3","id":"4"}}"#))
+				.unwrap();
+			tx.send(expect_op(&mut ws, 3)).unwrap();
+			tx.send(expect_op(&mut ws, 1)).unwrap();
+
+			// Drop the connection, which should be resumed
+			ws.close(Some(CloseFrame {
+				code: CloseCode::Library(4000),
+				reason: "".into(),
+			}))
+			.unwrap();
+			let _ = ws.flush();
+			let mut ws = tungstenite::accept(listener.accept().unwrap().0).unwrap();
+			ws.send(WsMessage::text(hello)).unwrap();
+			tx.send(expect_op(&mut ws, 6)).unwrap();
+			ws.send(WsMessage::text(r#"{"op":0,"s":3,"t":"RESUMED","d":{"_trace":[]}}"#)).unwrap();
+
+			// And closed normally once the stream is dropped
+			loop {
+				match ws.read() {
+					Ok(WsMessage::Close(frame)) => {
+						tx.send(json!(frame.map(|frame| u16::from(frame.code)))).unwrap();
+						break;
+					}
+					Ok(_) => {}
+					Err(_) => break,
+				}
+			}
+		});
+
+		let runtime = runtime();
+		let _runtime = runtime.enter();
+		let discord = AsyncDiscord::new(mock_discord(&base)).unwrap();
+		let (mut events, ready) = runtime.block_on(discord.connect()).unwrap();
+		assert_eq!(ready.session_id, "abc");
+		assert_eq!(received.recv().unwrap()["d"]["token"], "Bot token");
+		match runtime.block_on(events.next()) {
+			Some(Ok(Event::MessageDelete { message_id, .. })) => assert_eq!(message_id, MessageId(4)),
+			other => panic!("Expected MessageDelete, got {:?}", other),
+		}
+
+		events.set_presence(None, OnlineStatus::Idle, false);
+		match runtime.block_on(events.next()) {
+			Some(Ok(Event::Resumed { .. })) => {}
+			other => panic!("Expected Resumed, got {:?}", other),
+		}
+		assert_eq!(received.recv().unwrap()["d"]["status"], "idle");
+		assert_eq!(received.recv().unwrap()["d"], 2);
+		let resume = received.recv().unwrap();
+		assert_eq!(resume["d"]["session_id"], "abc");
+		assert_eq!(resume["d"]["seq"], 2);
+
+		drop(events);
+		let closed = runtime.block_on(task::spawn_blocking(move || received.recv().unwrap())).unwrap();
+		assert_eq!(closed, 1000);
+	}
+
+	#[cfg(feature = "voice")]
+	#[test]
+	fn voice_state() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let gateway = format!(r#"{{"url":"ws://{}"}}"#, listener.local_addr().unwrap());
+		let (base, _requests) = mock_server("200 OK", &gateway);
+		let (tx, received) = std_mpsc::channel();
+		thread::spawn(move || {
+			let mut ws = tungstenite::accept(listener.accept().unwrap().0).unwrap();
+			ws.send(WsMessage::text(r#"{"op":10,"d":{"heartbeat_interval":45000}}"#)).unwrap();
+			expect_op(&mut ws, 2);
+			ws.send(WsMessage::text(READY)).unwrap();
+			tx.send(expect_op(&mut ws, 4)).unwrap();
+			ws.send(WsMessage::text(
+				r#"{"op":0,"s":2,"t":"VOICE_STATE_UPDATE","d":{"guild_id":"5","channel_id":"6","user_id":"1",
+				"session_id":"def","suppress":false,"self_mute":false,"self_deaf":false,"mute":false,"deaf":false}}"#,
+			))
+			.unwrap();
+			// Dropping the stream leaves the channel before closing
+			tx.send(expect_op(&mut ws, 4)).unwrap();
+		});
+
+		let runtime = runtime();
+		let _runtime = runtime.enter();
+		let discord = AsyncDiscord::new(mock_discord(&base)).unwrap();
+		let (mut events, _ready) = runtime.block_on(discord.connect()).unwrap();
+		events.voice(Some(ServerId(5))).connect(ChannelId(6));
+		match runtime.block_on(events.next()) {
+			Some(Ok(Event::VoiceStateUpdate(..))) => {}
+			other => panic!("Expected VoiceStateUpdate, got {:?}", other),
+		}
+		assert_eq!(events.voice(Some(ServerId(5))).current_channel(), Some(ChannelId(6)));
+		let join = received.recv().unwrap();
+		assert_eq!(join["d"]["guild_id"], 5);
+		assert_eq!(join["d"]["channel_id"], 6);
+
+		drop(events);
+		let leave = runtime.block_on(task::spawn_blocking(move || received.recv().unwrap())).unwrap();
+		assert_eq!(leave["d"]["channel_id"], Value::Null);
+	}
+}
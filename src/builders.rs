@@ -59,6 +59,27 @@ builder! {
 	/// Patch content for the `edit_member` call.
 	EditMember(Object);
 
+	/// Content for the `start_thread` and `start_thread_from_message` calls.
+	CreateThread(Object);
+
+	/// Filter for the `get_audit_log` call.
+	GetAuditLog(Object);
+
+	/// Content for the `create_scheduled_event` and `edit_scheduled_event` calls.
+	EditScheduledEvent(Object);
+
+	/// Content for the `create_stage_instance` and `edit_stage_instance` calls.
+	EditStageInstance(Object);
+
+	/// Patch content for the `edit_own_voice_state` and `edit_voice_state` calls.
+	EditVoiceState(Object);
+
+	/// Content for the `create_server_sticker` and `edit_server_sticker` calls.
+	EditSticker(Object);
+
+	/// Content for the `create_server_template` and `edit_server_template` calls.
+	EditServerTemplate(Object);
+
 	/// Patch content for the `edit_profile` call.
 	EditProfile(Object);
 
@@ -85,6 +106,51 @@ builder! {
 
 	/// Inner patch content for the `send_embed` call.
 	EmbedFieldsBuilder(Vec<Value>);
+
+	/// Patch content for the `edit_webhook` call.
+	EditWebhook(Object);
+
+	/// Content for the `WebhookClient::execute` call.
+	ExecuteWebhook(Object);
+
+	/// Content for the `create_*_command` and `edit_*_command` calls.
+	CreateCommand(Object);
+
+	/// An option of an application command, for use within `CreateCommand`.
+	CreateCommandOption(Object);
+
+	/// Content for the `bulk_overwrite_*_commands` calls.
+	CreateCommands(Vec<Value>);
+
+	/// Content for the `create_interaction_response` call.
+	CreateInteractionResponse(Object);
+
+	/// Message content for interaction responses and follow-up messages.
+	InteractionMessage(Object);
+
+	/// Choices for use within `CreateInteractionResponse::autocomplete`.
+	AutocompleteChoices(Vec<Value>);
+
+	/// Modal content for use within `CreateInteractionResponse::modal`.
+	CreateModal(Object);
+
+	/// Text input for use within `CreateModal`.
+	CreateTextInput(Object);
+
+	/// Message components for use within `SendMessage` and similar builders.
+	CreateComponents(Vec<Value>);
+
+	/// Action row for use within `CreateComponents`.
+	CreateActionRow(Vec<Value>);
+
+	/// Button for use within `CreateActionRow`.
+	CreateButton(Object);
+
+	/// Select menu for use within `CreateActionRow`.
+	CreateSelectMenu(Object);
+
+	/// Select menu option for use within `CreateSelectMenu`.
+	CreateSelectOption(Object);
 }
 
 macro_rules! set {
@@ -93,6 +159,18 @@ macro_rules! set {
 	}}
 }
 
+macro_rules! push {
+	($self:ident, $key:expr, $($rest:tt)*) => {{
+		let mut s = $self;
+		let value = json!($($rest)*);
+		match s.0.entry($key).or_insert_with(|| Value::Array(Vec::new())) {
+			Value::Array(ref mut array) => array.push(value),
+			other => *other = Value::Array(vec![value]),
+		}
+		s
+	}}
+}
+
 impl EditServer {
 	/// Edit the server's name.
 	pub fn name(self, name: &str) -> Self {
@@ -160,6 +238,212 @@ impl EditChannel {
 	pub fn user_limit(self, user_limit: u64) -> Self {
 		set!(self, "user_limit", user_limit)
 	}
+
+	/// Archive or unarchive the thread.
+	pub fn archived(self, archived: bool) -> Self {
+		set!(self, "archived", archived)
+	}
+
+	/// Edit whether only moderators can unarchive the thread.
+	pub fn locked(self, locked: bool) -> Self {
+		set!(self, "locked", locked)
+	}
+
+	/// Edit the minutes of inactivity after which the thread is archived:
+	/// 60, 1440, 4320 or 10080.
+	pub fn auto_archive_duration(self, minutes: u64) -> Self {
+		set!(self, "auto_archive_duration", minutes)
+	}
+
+	/// Edit whether non-moderators can add members to the private thread.
+	pub fn invitable(self, invitable: bool) -> Self {
+		set!(self, "invitable", invitable)
+	}
+}
+
+impl CreateThread {
+	/// Set the thread's name, of 1-100 characters.
+	pub fn name(self, name: &str) -> Self {
+		set!(self, "name", name)
+	}
+
+	/// Set the minutes of inactivity after which the thread is archived:
+	/// 60, 1440, 4320 or 10080.
+	pub fn auto_archive_duration(self, minutes: u64) -> Self {
+		set!(self, "auto_archive_duration", minutes)
+	}
+
+	/// Set the type of a thread not started from a message. Defaults to
+	/// `PrivateThread`.
+	pub fn kind(self, kind: ChannelType) -> Self {
+		set!(self, "type", kind)
+	}
+
+	/// Set whether non-moderators can add members to a private thread.
+	pub fn invitable(self, invitable: bool) -> Self {
+		set!(self, "invitable", invitable)
+	}
+
+	/// Set the number of seconds users must wait between messages.
+	pub fn rate_limit_per_user(self, seconds: u64) -> Self {
+		set!(self, "rate_limit_per_user", seconds)
+	}
+}
+
+impl EditScheduledEvent {
+	/// Set the event's name, of 1-100 characters.
+	pub fn name(self, name: &str) -> Self {
+		set!(self, "name", name)
+	}
+
+	/// Set the event's description, of 1-1000 characters.
+	pub fn description(self, description: &str) -> Self {
+		set!(self, "description", description)
+	}
+
+	/// Set where the event takes place. A stage or voice channel must also
+	/// be given with `channel`, and an external event needs a `location` and
+	/// an end time.
+	pub fn entity_type(self, entity_type: ScheduledEventEntityType) -> Self {
+		set!(self, "entity_type", entity_type)
+	}
+
+	/// Hold the event in the given stage or voice channel.
+	pub fn channel(self, channel: ChannelId) -> Self {
+		set!(self, "channel_id", channel)
+	}
+
+	/// Hold the event at the given location outside Discord, of 1-100
+	/// characters. When moving an event out of a channel, `channel` is
+	/// cleared automatically.
+	pub fn location(self, location: &str) -> Self {
+		let mut s = set!(self, "entity_metadata", { "location": location });
+		if !s.0.contains_key("channel_id") {
+			s.0.insert("channel_id".into(), Value::Null);
+		}
+		s
+	}
+
+	/// Set when the event is scheduled to start.
+	pub fn scheduled_start_time(self, time: DateTime<FixedOffset>) -> Self {
+		set!(self, "scheduled_start_time", time.to_rfc3339())
+	}
+
+	/// Set when the event is scheduled to end.
+	pub fn scheduled_end_time(self, time: DateTime<FixedOffset>) -> Self {
+		set!(self, "scheduled_end_time", time.to_rfc3339())
+	}
+
+	/// Set who can see the event. Defaults to `ServerOnly`.
+	pub fn privacy_level(self, privacy_level: ScheduledEventPrivacyLevel) -> Self {
+		set!(self, "privacy_level", privacy_level)
+	}
+
+	/// Start, end or cancel the event. Only for `edit_scheduled_event`.
+	pub fn status(self, status: ScheduledEventStatus) -> Self {
+		set!(self, "status", status)
+	}
+
+	/// Set the event's cover image, as a data URI. Use `None` to remove it.
+	pub fn image(self, image: Option<&str>) -> Self {
+		set!(self, "image", image)
+	}
+}
+
+impl EditStageInstance {
+	/// Set the topic of the stage, of 1-120 characters.
+	pub fn topic(self, topic: &str) -> Self {
+		set!(self, "topic", topic)
+	}
+
+	/// Set who can see the stage. Defaults to `ServerOnly`.
+	pub fn privacy_level(self, privacy_level: StagePrivacyLevel) -> Self {
+		set!(self, "privacy_level", privacy_level)
+	}
+
+	/// Notify the server's members that the stage has started. Requires the
+	/// "MENTION_EVERYONE" permission. Only for `create_stage_instance`.
+	pub fn send_start_notification(self, notify: bool) -> Self {
+		set!(self, "send_start_notification", notify)
+	}
+
+	/// Associate the stage with a scheduled event. Only for
+	/// `create_stage_instance`.
+	pub fn scheduled_event(self, event: ScheduledEventId) -> Self {
+		set!(self, "guild_scheduled_event_id", event)
+	}
+}
+
+impl EditVoiceState {
+	/// Suppress or unsuppress the user, moving them between the audience and
+	/// the speakers of a stage.
+	pub fn suppress(self, suppress: bool) -> Self {
+		set!(self, "suppress", suppress)
+	}
+
+	/// Ask to speak in a stage as of the given time, or withdraw the request
+	/// with `None`. Only for `edit_own_voice_state`.
+	pub fn request_to_speak(self, timestamp: Option<DateTime<FixedOffset>>) -> Self {
+		set!(self, "request_to_speak_timestamp", timestamp.map(|t| t.to_rfc3339()))
+	}
+}
+
+impl EditSticker {
+	/// Set the sticker's name, of 2-30 characters.
+	pub fn name(self, name: &str) -> Self {
+		set!(self, "name", name)
+	}
+
+	/// Set the sticker's description, empty or of 2-100 characters.
+	pub fn description(self, description: &str) -> Self {
+		set!(self, "description", description)
+	}
+
+	/// Set the sticker's autocomplete keywords, of up to 200 characters.
+	pub fn tags(self, tags: &str) -> Self {
+		set!(self, "tags", tags)
+	}
+}
+
+impl EditServerTemplate {
+	/// Set the template's name, of 1-100 characters.
+	pub fn name(self, name: &str) -> Self {
+		set!(self, "name", name)
+	}
+
+	/// Set the template's description, of up to 120 characters. Use `None`
+	/// to remove it.
+	pub fn description(self, description: Option<&str>) -> Self {
+		set!(self, "description", description)
+	}
+}
+
+impl GetAuditLog {
+	/// Only include actions made by the given user.
+	pub fn user(self, user: UserId) -> Self {
+		set!(self, "user_id", user)
+	}
+
+	/// Only include actions of the given type.
+	pub fn action_type(self, action_type: AuditLogEvent) -> Self {
+		set!(self, "action_type", action_type)
+	}
+
+	/// Only include entries before the given entry, to page backwards.
+	pub fn before(self, entry: AuditLogEntryId) -> Self {
+		set!(self, "before", entry)
+	}
+
+	/// Only include entries after the given entry, to page forwards.
+	pub fn after(self, entry: AuditLogEntryId) -> Self {
+		set!(self, "after", entry)
+	}
+
+	/// Set the maximum number of entries to return, from 1 to 100. Defaults
+	/// to 50.
+	pub fn limit(self, limit: u64) -> Self {
+		set!(self, "limit", limit)
+	}
 }
 
 impl EditMember {
@@ -292,6 +576,20 @@ impl SendMessage {
 		}}).allowed_mentions(|b| b.replied_user(mention))
 	}
 
+	/// Set the message's action rows of buttons and select menus. See the
+	/// `CreateComponents` struct for the editable fields.
+	///
+	/// When editing, replaces the existing components; pass `|c| c` to remove
+	/// them.
+	pub fn components<F: FnOnce(CreateComponents) -> CreateComponents>(self, f: F) -> Self {
+		set!(self, "components", CreateComponents::__build(f))
+	}
+
+	/// Send a sticker with the message. Up to 3 may be sent.
+	pub fn sticker(self, sticker: StickerId) -> Self {
+		push!(self, "sticker_ids", sticker)
+	}
+
 	/// Change the message's flags.
 	///
 	/// Can only be set while editing. Only `SUPPRESS_EMBEDS` can be edited on
@@ -300,7 +598,20 @@ impl SendMessage {
 		set!(self, "flags", flags)
 	}
 
-	// TODO: file, payload_json, message_reference
+	/// Keep an existing attachment when editing a message.
+	///
+	/// When editing with `edit_message_with_files`, or after calling
+	/// `clear_attachments`, only the attachments named here are kept, in
+	/// addition to any newly uploaded files.
+	pub fn keep_attachment(self, attachment: &Attachment) -> Self {
+		push!(self, "attachments", { "id": attachment.id })
+	}
+
+	/// Remove all existing attachments not kept using `keep_attachment` when
+	/// editing a message.
+	pub fn clear_attachments(self) -> Self {
+		set!(self, "attachments", [])
+	}
 }
 
 impl AllowedMentions {
@@ -364,6 +675,474 @@ impl EmbedBuilder {
 	}
 }
 
+impl EditWebhook {
+	/// Edit the webhook's default name.
+	pub fn name(self, name: &str) -> Self {
+		set!(self, "name", name)
+	}
+
+	/// Edit the webhook's default avatar. Use `None` to remove the avatar.
+	///
+	/// `read_image` may be used to build an `avatar` string.
+	pub fn avatar(self, avatar: Option<&str>) -> Self {
+		set!(self, "avatar", avatar)
+	}
+
+	/// Move the webhook to another channel. Not available when editing with
+	/// the webhook's token.
+	pub fn channel(self, channel: ChannelId) -> Self {
+		set!(self, "channel_id", channel)
+	}
+}
+
+impl ExecuteWebhook {
+	/// Set the text content of the message.
+	pub fn content(self, content: &str) -> Self {
+		set!(self, "content", content)
+	}
+
+	/// Override the webhook's default username.
+	pub fn username(self, username: &str) -> Self {
+		set!(self, "username", username)
+	}
+
+	/// Override the webhook's default avatar with an image URL.
+	pub fn avatar_url(self, avatar_url: &str) -> Self {
+		set!(self, "avatar_url", avatar_url)
+	}
+
+	/// Set to true to use text-to-speech.
+	pub fn tts(self, tts: bool) -> Self {
+		set!(self, "tts", tts)
+	}
+
+	/// Add an embed to the message. May be called up to 10 times.
+	pub fn embed<F: FnOnce(EmbedBuilder) -> EmbedBuilder>(self, f: F) -> Self {
+		push!(self, "embeds", EmbedBuilder::__build(f))
+	}
+
+	/// Restrict allowed mentions for this message.
+	pub fn allowed_mentions<F: FnOnce(AllowedMentions) -> AllowedMentions>(self, f: F) -> Self {
+		set!(self, "allowed_mentions", AllowedMentions::__build(f))
+	}
+
+	/// Change the message's flags. Only `SUPPRESS_EMBEDS` can be set.
+	pub fn flags(self, flags: MessageFlags) -> Self {
+		set!(self, "flags", flags)
+	}
+
+	/// Set the message's action rows of buttons and select menus. Only
+	/// available for webhooks owned by an application. See the
+	/// `CreateComponents` struct for the editable fields.
+	pub fn components<F: FnOnce(CreateComponents) -> CreateComponents>(self, f: F) -> Self {
+		set!(self, "components", CreateComponents::__build(f))
+	}
+}
+
+impl CreateCommand {
+	/// Set the command's name, of 1-32 characters.
+	///
+	/// Slash command names must be lowercase.
+	pub fn name(self, name: &str) -> Self {
+		set!(self, "name", name)
+	}
+
+	/// Set the command's description, of 1-100 characters. Must be empty for
+	/// user and message commands.
+	pub fn description(self, description: &str) -> Self {
+		set!(self, "description", description)
+	}
+
+	/// Set the type of the command. Defaults to `ChatInput`.
+	pub fn kind(self, kind: ApplicationCommandType) -> Self {
+		set!(self, "type", kind)
+	}
+
+	/// Add a parameter to the command. May be called up to 25 times.
+	/// See the `CreateCommandOption` struct for the editable fields.
+	pub fn option<F: FnOnce(CreateCommandOption) -> CreateCommandOption>(self, f: F) -> Self {
+		push!(self, "options", CreateCommandOption::__build(f))
+	}
+
+	/// Set the permissions a member needs to use the command by default.
+	/// Use `None` to allow everyone, and an empty set to allow only
+	/// administrators.
+	pub fn default_member_permissions(self, permissions: Option<Permissions>) -> Self {
+		set!(self, "default_member_permissions", permissions.map(|p| p.bits().to_string()))
+	}
+
+	/// Set whether the command is available in private channels. Only
+	/// applies to global commands.
+	pub fn dm_permission(self, dm_permission: bool) -> Self {
+		set!(self, "dm_permission", dm_permission)
+	}
+}
+
+impl CreateCommandOption {
+	/// Set the type of the option.
+	pub fn kind(self, kind: ApplicationCommandOptionType) -> Self {
+		set!(self, "type", kind)
+	}
+
+	/// Set the option's name, of 1-32 lowercase characters.
+	pub fn name(self, name: &str) -> Self {
+		set!(self, "name", name)
+	}
+
+	/// Set the option's description, of 1-100 characters.
+	pub fn description(self, description: &str) -> Self {
+		set!(self, "description", description)
+	}
+
+	/// Set whether the option must be filled in. Defaults to `false`.
+	pub fn required(self, required: bool) -> Self {
+		set!(self, "required", required)
+	}
+
+	/// Add a predefined value for the user to pick. The value should be a
+	/// string, integer, or number matching the option's type. May be called
+	/// up to 25 times.
+	pub fn choice<V: Into<Value>>(self, name: &str, value: V) -> Self {
+		push!(self, "choices", { "name": name, "value": value.into() })
+	}
+
+	/// Add a parameter to a subcommand, or a subcommand to a subcommand
+	/// group.
+	pub fn option<F: FnOnce(CreateCommandOption) -> CreateCommandOption>(self, f: F) -> Self {
+		push!(self, "options", CreateCommandOption::__build(f))
+	}
+
+	/// Restrict a `Channel` option to the given channel types.
+	pub fn channel_types(self, channel_types: &[ChannelType]) -> Self {
+		set!(self, "channel_types", channel_types)
+	}
+
+	/// Set the minimum value of an `Integer` or `Number` option.
+	pub fn min_value<V: Into<Value>>(self, min_value: V) -> Self {
+		set!(self, "min_value", min_value.into())
+	}
+
+	/// Set the maximum value of an `Integer` or `Number` option.
+	pub fn max_value<V: Into<Value>>(self, max_value: V) -> Self {
+		set!(self, "max_value", max_value.into())
+	}
+
+	/// Set to `true` to receive autocomplete interactions for this option.
+	/// May not be combined with choices.
+	pub fn autocomplete(self, autocomplete: bool) -> Self {
+		set!(self, "autocomplete", autocomplete)
+	}
+}
+
+impl CreateCommands {
+	/// Add a command to the list. Commands missing from the list are
+	/// deleted. See the `CreateCommand` struct for the editable fields.
+	pub fn command<F: FnOnce(CreateCommand) -> CreateCommand>(mut self, f: F) -> Self {
+		self.0.push(Value::Object(CreateCommand::__build(f)));
+		self
+	}
+}
+
+impl CreateInteractionResponse {
+	/// Acknowledge a ping. Only needed when receiving interactions over HTTP.
+	pub fn pong(self) -> Self {
+		set!(self, "type", InteractionResponseType::Pong)
+	}
+
+	/// Respond with a message. See the `InteractionMessage` struct for the
+	/// editable fields.
+	pub fn message<F: FnOnce(InteractionMessage) -> InteractionMessage>(self, f: F) -> Self {
+		let s = set!(self, "type", InteractionResponseType::ChannelMessageWithSource);
+		set!(s, "data", InteractionMessage::__build(f))
+	}
+
+	/// Acknowledge the interaction and show a loading state. The message can
+	/// be sent later using `edit_original_response`.
+	pub fn deferred_message(self, ephemeral: bool) -> Self {
+		let s = set!(self, "type", InteractionResponseType::DeferredChannelMessageWithSource);
+		if ephemeral {
+			set!(s, "data", { "flags": MessageFlags::EPHEMERAL })
+		} else {
+			s
+		}
+	}
+
+	/// Acknowledge a component interaction without editing its message yet.
+	pub fn deferred_update(self) -> Self {
+		set!(self, "type", InteractionResponseType::DeferredUpdateMessage)
+	}
+
+	/// Edit the message a component was attached to. See the
+	/// `InteractionMessage` struct for the editable fields.
+	pub fn update_message<F: FnOnce(InteractionMessage) -> InteractionMessage>(self, f: F) -> Self {
+		let s = set!(self, "type", InteractionResponseType::UpdateMessage);
+		set!(s, "data", InteractionMessage::__build(f))
+	}
+
+	/// Suggest choices for the option being autocompleted.
+	pub fn autocomplete<F: FnOnce(AutocompleteChoices) -> AutocompleteChoices>(self, f: F) -> Self {
+		let s = set!(self, "type", InteractionResponseType::ApplicationCommandAutocompleteResult);
+		set!(s, "data", { "choices": AutocompleteChoices::__build(f) })
+	}
+
+	/// Respond with a popup modal. See the `CreateModal` struct for the
+	/// editable fields. Not available for modal submissions.
+	pub fn modal<F: FnOnce(CreateModal) -> CreateModal>(self, f: F) -> Self {
+		let s = set!(self, "type", InteractionResponseType::Modal);
+		set!(s, "data", CreateModal::__build(f))
+	}
+}
+
+impl InteractionMessage {
+	/// Set the text content of the message.
+	pub fn content(self, content: &str) -> Self {
+		set!(self, "content", content)
+	}
+
+	/// Set to true to use text-to-speech.
+	pub fn tts(self, tts: bool) -> Self {
+		set!(self, "tts", tts)
+	}
+
+	/// Add an embed to the message. May be called up to 10 times.
+	pub fn embed<F: FnOnce(EmbedBuilder) -> EmbedBuilder>(self, f: F) -> Self {
+		push!(self, "embeds", EmbedBuilder::__build(f))
+	}
+
+	/// Restrict allowed mentions for this message.
+	pub fn allowed_mentions<F: FnOnce(AllowedMentions) -> AllowedMentions>(self, f: F) -> Self {
+		set!(self, "allowed_mentions", AllowedMentions::__build(f))
+	}
+
+	/// Set the message's flags. `EPHEMERAL` makes the message visible only to
+	/// the user who used the interaction.
+	pub fn flags(self, flags: MessageFlags) -> Self {
+		set!(self, "flags", flags)
+	}
+
+	/// Set the message's action rows of buttons and select menus. See the
+	/// `CreateComponents` struct for the editable fields.
+	///
+	/// When editing, replaces the existing components; pass `|c| c` to remove
+	/// them.
+	pub fn components<F: FnOnce(CreateComponents) -> CreateComponents>(self, f: F) -> Self {
+		set!(self, "components", CreateComponents::__build(f))
+	}
+}
+
+impl AutocompleteChoices {
+	/// Add a choice. The value should be a string, integer, or number
+	/// matching the option's type. May be called up to 25 times.
+	pub fn choice<V: Into<Value>>(mut self, name: &str, value: V) -> Self {
+		self.0.push(json! {{
+			"name": name,
+			"value": value.into(),
+		}});
+		self
+	}
+}
+
+impl CreateModal {
+	/// Set the developer-defined identifier returned when the modal is
+	/// submitted.
+	pub fn custom_id(self, custom_id: &str) -> Self {
+		set!(self, "custom_id", custom_id)
+	}
+
+	/// Set the title of the popup.
+	pub fn title(self, title: &str) -> Self {
+		set!(self, "title", title)
+	}
+
+	/// Add a text input on its own row. May be called up to 5 times. See the
+	/// `CreateTextInput` struct for the editable fields.
+	pub fn text_input<F: FnOnce(CreateTextInput) -> CreateTextInput>(self, f: F) -> Self {
+		let mut input = CreateTextInput::__build(f);
+		input.insert("type".into(), json!(ComponentType::TextInput));
+		push!(self, "components", {
+			"type": ComponentType::ActionRow,
+			"components": [input],
+		})
+	}
+}
+
+impl CreateTextInput {
+	/// Set the developer-defined identifier of the input.
+	pub fn custom_id(self, custom_id: &str) -> Self {
+		set!(self, "custom_id", custom_id)
+	}
+
+	/// Set the label shown above the input.
+	pub fn label(self, label: &str) -> Self {
+		set!(self, "label", label)
+	}
+
+	/// Set whether the input is a single line or a paragraph.
+	pub fn style(self, style: TextInputStyle) -> Self {
+		set!(self, "style", style)
+	}
+
+	/// Set the minimum length of the input, from 0 to 4000.
+	pub fn min_length(self, min_length: u64) -> Self {
+		set!(self, "min_length", min_length)
+	}
+
+	/// Set the maximum length of the input, from 1 to 4000.
+	pub fn max_length(self, max_length: u64) -> Self {
+		set!(self, "max_length", max_length)
+	}
+
+	/// Set whether the input must be filled in. Defaults to `true`.
+	pub fn required(self, required: bool) -> Self {
+		set!(self, "required", required)
+	}
+
+	/// Pre-fill the input with a value.
+	pub fn value(self, value: &str) -> Self {
+		set!(self, "value", value)
+	}
+
+	/// Set the placeholder shown while the input is empty.
+	pub fn placeholder(self, placeholder: &str) -> Self {
+		set!(self, "placeholder", placeholder)
+	}
+}
+
+impl CreateComponents {
+	/// Add a row of components. May be called up to 5 times. See the
+	/// `CreateActionRow` struct for the editable fields.
+	pub fn action_row<F: FnOnce(CreateActionRow) -> CreateActionRow>(mut self, f: F) -> Self {
+		self.0.push(json! {{
+			"type": ComponentType::ActionRow,
+			"components": CreateActionRow::__build(f),
+		}});
+		self
+	}
+}
+
+impl CreateActionRow {
+	/// Add a button. A row may hold up to 5 buttons, and no select menu. See
+	/// the `CreateButton` struct for the editable fields.
+	pub fn button<F: FnOnce(CreateButton) -> CreateButton>(mut self, f: F) -> Self {
+		let mut button = CreateButton::__build(f);
+		button.insert("type".into(), json!(ComponentType::Button));
+		self.0.push(Value::Object(button));
+		self
+	}
+
+	/// Add a select menu. A row holding a select menu may hold nothing else.
+	/// See the `CreateSelectMenu` struct for the editable fields.
+	pub fn select_menu<F: FnOnce(CreateSelectMenu) -> CreateSelectMenu>(mut self, f: F) -> Self {
+		let mut menu = CreateSelectMenu::__build(f);
+		menu.entry("type").or_insert_with(|| json!(ComponentType::StringSelect));
+		self.0.push(Value::Object(menu));
+		self
+	}
+}
+
+impl CreateButton {
+	/// Set the button's appearance. `Link` buttons require a `url`, and all
+	/// others a `custom_id`.
+	pub fn style(self, style: ButtonStyle) -> Self {
+		set!(self, "style", style)
+	}
+
+	/// Set the text shown on the button.
+	pub fn label(self, label: &str) -> Self {
+		set!(self, "label", label)
+	}
+
+	/// Set the emoji shown on the button.
+	pub fn emoji(self, emoji: ReactionEmoji) -> Self {
+		set!(self, "emoji", emoji)
+	}
+
+	/// Set the developer-defined identifier sent with interactions.
+	pub fn custom_id(self, custom_id: &str) -> Self {
+		set!(self, "custom_id", custom_id)
+	}
+
+	/// Set the URL opened by a `Link` button.
+	pub fn url(self, url: &str) -> Self {
+		set!(self, "url", url)
+	}
+
+	/// Set whether the button is greyed out.
+	pub fn disabled(self, disabled: bool) -> Self {
+		set!(self, "disabled", disabled)
+	}
+}
+
+impl CreateSelectMenu {
+	/// Set the type of the menu. Defaults to `StringSelect`.
+	pub fn kind(self, kind: ComponentType) -> Self {
+		set!(self, "type", kind)
+	}
+
+	/// Set the developer-defined identifier sent with interactions.
+	pub fn custom_id(self, custom_id: &str) -> Self {
+		set!(self, "custom_id", custom_id)
+	}
+
+	/// Set the text shown while nothing is selected.
+	pub fn placeholder(self, placeholder: &str) -> Self {
+		set!(self, "placeholder", placeholder)
+	}
+
+	/// Set the minimum number of items which must be chosen, from 0 to 25.
+	pub fn min_values(self, min_values: u64) -> Self {
+		set!(self, "min_values", min_values)
+	}
+
+	/// Set the maximum number of items which may be chosen, up to 25.
+	pub fn max_values(self, max_values: u64) -> Self {
+		set!(self, "max_values", max_values)
+	}
+
+	/// Set whether the menu is greyed out.
+	pub fn disabled(self, disabled: bool) -> Self {
+		set!(self, "disabled", disabled)
+	}
+
+	/// Add a choice to a `StringSelect` menu. May be called up to 25 times.
+	/// See the `CreateSelectOption` struct for the editable fields.
+	pub fn option<F: FnOnce(CreateSelectOption) -> CreateSelectOption>(self, f: F) -> Self {
+		push!(self, "options", CreateSelectOption::__build(f))
+	}
+
+	/// Restrict a `ChannelSelect` menu to the given channel types.
+	pub fn channel_types(self, channel_types: &[ChannelType]) -> Self {
+		set!(self, "channel_types", channel_types)
+	}
+}
+
+impl CreateSelectOption {
+	/// Set the text shown for the option.
+	pub fn label(self, label: &str) -> Self {
+		set!(self, "label", label)
+	}
+
+	/// Set the value sent with interactions when the option is chosen.
+	pub fn value(self, value: &str) -> Self {
+		set!(self, "value", value)
+	}
+
+	/// Set the additional description shown below the label.
+	pub fn description(self, description: &str) -> Self {
+		set!(self, "description", description)
+	}
+
+	/// Set the emoji shown for the option.
+	pub fn emoji(self, emoji: ReactionEmoji) -> Self {
+		set!(self, "emoji", emoji)
+	}
+
+	/// Set whether the option is chosen by default.
+	pub fn default(self, default: bool) -> Self {
+		set!(self, "default", default)
+	}
+}
+
 impl EmbedFooterBuilder {
 	/// Add the "footer text".
 	pub fn text(self, text: &str) -> Self {
@@ -0,0 +1,252 @@
+//! URLs of avatars, icons and other images on Discord's CDN.
+//!
+//! Each function returns an `ImageUrl`, which can be formatted as a string or
+//! adjusted first. Animated images are detected from their hash and served as
+//! GIFs unless another format is requested:
+//!
+//! ```ignore
+//! let url = cdn::user_avatar(user.id, &hash).format(ImageFormat::WebP).size(128);
+//! let bytes = discord.download_cdn(&url.to_string())?;
+//! ```
+//!
+//! Model types such as `User`, `Server` and `Emoji` have methods which call
+//! these functions.
+
+use std::fmt;
+
+use model::*;
+
+/// The default base URL of the CDN.
+///
+/// Out of everything, only the CDN still uses the old domain.
+pub const CDN_BASE: &'static str = "https://cdn.discordapp.com";
+
+/// The file format in which to request an image.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+	/// The default for images which are not animated
+	Png,
+	/// Smaller, but lossy and without transparency
+	Jpeg,
+	/// Smaller, but giving only the first frame of animated images
+	WebP,
+	/// Only available for animated images
+	Gif,
+}
+
+impl ImageFormat {
+	/// Get the file extension for this format.
+	pub fn extension(self) -> &'static str {
+		match self {
+			ImageFormat::Png => "png",
+			ImageFormat::Jpeg => "jpg",
+			ImageFormat::WebP => "webp",
+			ImageFormat::Gif => "gif",
+		}
+	}
+}
+
+/// The URL of an image on the CDN, with an optional format and size.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ImageUrl {
+	base: String,
+	path: String,
+	animated: bool,
+	format: Option<ImageFormat>,
+	size: Option<u16>,
+}
+
+impl ImageUrl {
+	/// An image at the given path relative to `CDN_BASE`, without extension.
+	pub fn new(path: String, animated: bool) -> ImageUrl {
+		ImageUrl {
+			base: CDN_BASE.to_owned(),
+			path: path,
+			animated: animated,
+			format: None,
+			size: None,
+		}
+	}
+
+	/// Whether the image is animated.
+	pub fn is_animated(&self) -> bool {
+		self.animated
+	}
+
+	/// Request the image from a CDN other than `CDN_BASE`, such as one set
+	/// with `DiscordBuilder::with_cdn_base`.
+	///
+	/// The base should not end with a slash.
+	pub fn base(mut self, base: &str) -> Self {
+		self.base = base.trim_end_matches('/').to_owned();
+		self
+	}
+
+	/// Request the image in the given format.
+	///
+	/// Defaults to GIF for animated images and PNG otherwise. Requesting a
+	/// GIF of an image which is not animated gives a PNG instead, and
+	/// requesting another format of an animated image gives its first frame.
+	pub fn format(mut self, format: ImageFormat) -> Self {
+		self.format = Some(format);
+		self
+	}
+
+	/// Request the image at the given size in pixels, which is rounded up to
+	/// a power of two between 16 and 4096.
+	pub fn size(mut self, size: u16) -> Self {
+		self.size = Some(size.max(16).min(4096).next_power_of_two());
+		self
+	}
+
+	/// Get the format in which the image will be requested.
+	pub fn effective_format(&self) -> ImageFormat {
+		match (self.format, self.animated) {
+			(Some(ImageFormat::Gif), false) | (None, false) => ImageFormat::Png,
+			(Some(format), _) => format,
+			(None, true) => ImageFormat::Gif,
+		}
+	}
+}
+
+impl fmt::Display for ImageUrl {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}{}.{}", self.base, self.path, self.effective_format().extension())?;
+		if let Some(size) = self.size {
+			write!(f, "?size={}", size)?;
+		}
+		Ok(())
+	}
+}
+
+impl From<ImageUrl> for String {
+	fn from(url: ImageUrl) -> String {
+		url.to_string()
+	}
+}
+
+/// Whether an image hash refers to an animated image.
+pub fn is_animated(hash: &str) -> bool {
+	hash.starts_with("a_")
+}
+
+fn hashed(path: String, hash: &str) -> ImageUrl {
+	ImageUrl::new(path, is_animated(hash))
+}
+
+/// A user's avatar.
+pub fn user_avatar(user: UserId, hash: &str) -> ImageUrl {
+	hashed(format!("/avatars/{}/{}", user, hash), hash)
+}
+
+/// A webhook's avatar.
+pub fn webhook_avatar(webhook: WebhookId, hash: &str) -> ImageUrl {
+	hashed(format!("/avatars/{}/{}", webhook, hash), hash)
+}
+
+/// The default avatar of a user who has not set one, which is only
+/// available as a PNG.
+///
+/// Users who have migrated to unique usernames have a discriminator of 0.
+pub fn default_avatar(user: UserId, discriminator: u16) -> ImageUrl {
+	let index = match discriminator {
+		0 => (user.0 >> 22) % 6,
+		discriminator => discriminator as u64 % 5,
+	};
+	ImageUrl::new(format!("/embed/avatars/{}", index), false).format(ImageFormat::Png)
+}
+
+/// A member's server-specific avatar.
+pub fn member_avatar(server: ServerId, user: UserId, hash: &str) -> ImageUrl {
+	hashed(format!("/guilds/{}/users/{}/avatars/{}", server, user, hash), hash)
+}
+
+/// A server's icon.
+pub fn server_icon(server: ServerId, hash: &str) -> ImageUrl {
+	hashed(format!("/icons/{}/{}", server, hash), hash)
+}
+
+/// A server's invite splash image.
+pub fn server_splash(server: ServerId, hash: &str) -> ImageUrl {
+	hashed(format!("/splashes/{}/{}", server, hash), hash)
+}
+
+/// A server's discovery splash image.
+pub fn server_discovery_splash(server: ServerId, hash: &str) -> ImageUrl {
+	hashed(format!("/discovery-splashes/{}/{}", server, hash), hash)
+}
+
+/// A server's banner.
+pub fn server_banner(server: ServerId, hash: &str) -> ImageUrl {
+	hashed(format!("/banners/{}/{}", server, hash), hash)
+}
+
+/// A user's profile banner.
+pub fn user_banner(user: UserId, hash: &str) -> ImageUrl {
+	hashed(format!("/banners/{}/{}", user, hash), hash)
+}
+
+/// A group channel's icon.
+pub fn group_icon(channel: ChannelId, hash: &str) -> ImageUrl {
+	hashed(format!("/channel-icons/{}/{}", channel, hash), hash)
+}
+
+/// A role's icon.
+pub fn role_icon(role: RoleId, hash: &str) -> ImageUrl {
+	hashed(format!("/role-icons/{}/{}", role, hash), hash)
+}
+
+/// A custom emoji, whose animation is known from the emoji itself.
+pub fn emoji(emoji: EmojiId, animated: bool) -> ImageUrl {
+	ImageUrl::new(format!("/emojis/{}", emoji), animated)
+}
+
+/// An application's icon.
+pub fn application_icon(application: ApplicationId, hash: &str) -> ImageUrl {
+	hashed(format!("/app-icons/{}/{}", application, hash), hash)
+}
+
+/// The cover image of a scheduled event.
+pub fn scheduled_event_cover(event: ScheduledEventId, hash: &str) -> ImageUrl {
+	hashed(format!("/guild-events/{}/{}", event, hash), hash)
+}
+
+/// A sticker, in the format it was uploaded in: PNG for PNG and APNG
+/// stickers, JSON for Lottie stickers, or GIF.
+pub fn sticker(sticker: StickerId, format: StickerFormatType) -> String {
+	let extension = match format {
+		StickerFormatType::Lottie => "json",
+		StickerFormatType::Gif => "gif",
+		_ => "png",
+	};
+	format!("{}/stickers/{}.{}", CDN_BASE, sticker, extension)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn image_urls() {
+		let avatar = user_avatar(UserId(1), "a_abc");
+		assert!(avatar.is_animated());
+		assert_eq!(avatar.to_string(), "https://cdn.discordapp.com/avatars/1/a_abc.gif");
+		assert_eq!(
+			avatar.format(ImageFormat::WebP).size(100).to_string(),
+			"https://cdn.discordapp.com/avatars/1/a_abc.webp?size=128"
+		);
+		assert_eq!(
+			server_icon(ServerId(2), "def").format(ImageFormat::Gif).size(9000).to_string(),
+			"https://cdn.discordapp.com/icons/2/def.png?size=4096"
+		);
+		assert_eq!(
+			default_avatar(UserId(3), 1337).to_string(),
+			"https://cdn.discordapp.com/embed/avatars/2.png"
+		);
+		assert_eq!(emoji(EmojiId(4), true).size(1).to_string(), "https://cdn.discordapp.com/emojis/4.gif?size=16");
+		assert_eq!(
+			webhook_avatar(WebhookId(5), "ghi").base("http://localhost/").to_string(),
+			"http://localhost/avatars/5/ghi.png"
+		);
+	}
+}
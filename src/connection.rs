@@ -2,18 +2,18 @@
 use std::collections::HashMap;
 use std::sync::mpsc;
 
-use websocket::client::{Client, Receiver, Sender};
-use websocket::stream::WebSocketStream;
+use websocket::client::{Receiver, Sender};
 
 use serde_json;
 
 use internal::Status;
 use model::*;
+use proxy::{self, WsStream};
 #[cfg(feature = "voice")]
 use voice::VoiceConnection;
-use {Error, ReceiverExt, Result, SenderExt};
+use {DiscordBuilder, Error, ReceiverExt, Result, SenderExt};
 
-const GATEWAY_VERSION: u64 = 6;
+pub(crate) const GATEWAY_VERSION: u64 = 6;
 
 #[cfg(feature = "voice")]
 macro_rules! finish_connection {
@@ -33,6 +33,7 @@ macro_rules! finish_connection {
 pub struct ConnectionBuilder<'a> {
 	base_url: String,
 	token: &'a str,
+	config: &'a DiscordBuilder,
 
 	//large_threshold: Option<u32>,
 	shard: Option<[u8; 2]>,
@@ -41,10 +42,11 @@ pub struct ConnectionBuilder<'a> {
 }
 
 impl<'a> ConnectionBuilder<'a> {
-	pub(crate) fn new(base_url: String, token: &'a str) -> Self {
+	pub(crate) fn new(base_url: String, token: &'a str, config: &'a DiscordBuilder) -> Self {
 		ConnectionBuilder {
 			base_url,
 			token,
+			config,
 			//large_threshold: None,
 			shard: None,
 			intents: None,
@@ -69,6 +71,17 @@ impl<'a> ConnectionBuilder<'a> {
 	/// Also returns the `ReadyEvent` sent by Discord upon establishing the
 	/// connection, which contains the initial state as seen by the client.
 	pub fn connect(&self) -> Result<(Connection, ReadyEvent)> {
+		Connection::__connect(&self.base_url, self.token, self.identify(), self.config)
+	}
+
+	/// The gateway URL to connect to.
+	#[cfg(feature = "async")]
+	pub(crate) fn base_url(&self) -> &str {
+		&self.base_url
+	}
+
+	/// The Identify message to send upon connecting.
+	pub(crate) fn identify(&self) -> serde_json::Value {
 		let mut d = json! {{
 			"token": self.token,
 			"properties": {
@@ -88,18 +101,17 @@ impl<'a> ConnectionBuilder<'a> {
 		if let Some(intents) = self.intents {
 			d["intents"] = intents.bits().into();
 		}
-		let identify = json! {{
+		json! {{
 			"op": 2,
 			"d": d
-		}};
-		Connection::__connect(&self.base_url, self.token.clone(), identify)
+		}}
 	}
 }
 
 /// Websocket connection to the Discord servers.
 pub struct Connection {
 	keepalive_channel: mpsc::Sender<Status>,
-	receiver: Receiver<WebSocketStream>,
+	receiver: Receiver<WsStream>,
 	#[cfg(feature = "voice")]
 	voice_handles: HashMap<Option<ServerId>, VoiceConnection>,
 	#[cfg(feature = "voice")]
@@ -109,6 +121,7 @@ pub struct Connection {
 	session_id: Option<String>,
 	last_sequence: u64,
 	identify: serde_json::Value,
+	config: DiscordBuilder,
 }
 
 impl Connection {
@@ -125,16 +138,15 @@ impl Connection {
 		token: &str,
 		shard: Option<[u8; 2]>,
 	) -> Result<(Connection, ReadyEvent)> {
-		ConnectionBuilder { shard, .. ConnectionBuilder::new(base_url.to_owned(), token) }.connect()
+		let config = DiscordBuilder::new();
+		ConnectionBuilder { shard, .. ConnectionBuilder::new(base_url.to_owned(), token, &config) }.connect()
 	}
 
-	fn __connect(base_url: &str, token: &str, identify: serde_json::Value) -> Result<(Connection, ReadyEvent)> {
+	fn __connect(base_url: &str, token: &str, identify: serde_json::Value, config: &DiscordBuilder) -> Result<(Connection, ReadyEvent)> {
 		trace!("Gateway: {}", base_url);
 		// establish the websocket connection
 		let url = build_gateway_url(base_url)?;
-		let response = Client::connect(url)?.send()?;
-		response.validate()?;
-		let (mut sender, mut receiver) = response.begin().split();
+		let (mut sender, mut receiver) = proxy::connect_websocket(url, config.proxy.as_ref())?;
 
 		// send the handshake
 		sender.send_json(&identify)?;
@@ -206,7 +218,8 @@ impl Connection {
 				token: token.to_owned(),
 				session_id: Some(session_id),
 				last_sequence: sequence,
-				identify: identify;
+				identify: identify,
+				config: config.clone();
 				// voice only
 				user_id: ready.user.id,
 				voice_handles: HashMap::new(),
@@ -231,29 +244,7 @@ impl Connection {
 	///
 	/// `afk` will help Discord determine where to send notifications.
 	pub fn set_presence(&self, game: Option<Game>, status: OnlineStatus, afk: bool) {
-		let status = match status {
-			OnlineStatus::Offline => OnlineStatus::Invisible,
-			other => other,
-		};
-		let game = match game {
-			Some(Game {
-				kind: GameType::Streaming,
-				url: Some(url),
-				name,
-			}) => json! {{ "type": GameType::Streaming, "url": url, "name": name }},
-			Some(game) => json! {{ "name": game.name, "type": GameType::Playing }},
-			None => json!(null),
-		};
-		let msg = json! {{
-			"op": 3,
-			"d": {
-				"afk": afk,
-				"since": 0,
-				"status": status,
-				"game": game,
-			}
-		}};
-		let _ = self.keepalive_channel.send(Status::SendMessage(msg));
+		let _ = self.keepalive_channel.send(Status::SendMessage(presence_message(game, status, afk)));
 	}
 
 	/// Get a handle to the voice connection for a server.
@@ -265,10 +256,11 @@ impl Connection {
 			ref mut voice_handles,
 			user_id,
 			ref keepalive_channel,
+			ref config,
 			..
 		} = *self;
 		voice_handles.entry(server_id).or_insert_with(|| {
-			VoiceConnection::__new(server_id, user_id, keepalive_channel.clone())
+			VoiceConnection::__new(server_id, user_id, keepalive_channel.clone(), config.proxy.clone())
 		})
 	}
 
@@ -369,7 +361,7 @@ impl Connection {
 		trace!("Reconnecting...");
 		// Make two attempts on the current known gateway URL
 		for _ in 0..2 {
-			if let Ok((conn, ready)) = Connection::__connect(&self.ws_url, &self.token, self.identify.clone()) {
+			if let Ok((conn, ready)) = Connection::__connect(&self.ws_url, &self.token, self.identify.clone(), &self.config) {
 				::std::mem::replace(self, conn).raw_shutdown();
 				self.session_id = Some(ready.session_id.clone());
 				return Ok(ready);
@@ -378,8 +370,8 @@ impl Connection {
 		}
 
 		// If those fail, hit REST for a new endpoint
-		let url = ::Discord::from_token_raw(self.token.to_owned()).get_gateway_url()?;
-		let (conn, ready) = Connection::__connect(&url, &self.token, self.identify.clone())?;
+		let url = self.config.build(self.token.to_owned())?.get_gateway_url()?;
+		let (conn, ready) = Connection::__connect(&url, &self.token, self.identify.clone(), &self.config)?;
 		::std::mem::replace(self, conn).raw_shutdown();
 		self.session_id = Some(ready.session_id.clone());
 		Ok(ready)
@@ -395,9 +387,7 @@ impl Connection {
 			.get_mut()
 			.shutdown(::std::net::Shutdown::Both)?;
 		let url = build_gateway_url(&self.ws_url)?;
-		let response = Client::connect(url)?.send()?;
-		response.validate()?;
-		let (mut sender, mut receiver) = response.begin().split();
+		let (mut sender, mut receiver) = proxy::connect_websocket(url, self.config.proxy.as_ref())?;
 
 		// send the resume request
 		let resume = json! {{
@@ -459,7 +449,7 @@ impl Connection {
 		use std::io::Write;
 		use websocket::Sender as S;
 
-		// Hacky horror: get the WebSocketStream from the Receiver and formally close it
+		// Hacky horror: get the WsStream from the Receiver and formally close it
 		let stream = self.receiver.get_mut().get_mut();
 		Sender::new(stream.by_ref(), true)
 			.send_message(&::websocket::message::Message::close_because(1000, ""))?;
@@ -515,19 +505,9 @@ impl Connection {
 	/// The members lists are cleared on call, and then refilled as chunks are received. When
 	/// `unknown_members()` returns 0, the download has completed.
 	pub fn download_all_members(&mut self, state: &mut ::State) {
-		if state.unknown_members() == 0 {
-			return;
+		if let Some(msg) = request_members_message(state) {
+			let _ = self.keepalive_channel.send(Status::SendMessage(msg));
 		}
-		let servers = state.__download_members();
-		let msg = json! {{
-			"op": 8,
-			"d": {
-				"guild_id": servers,
-				"query": "",
-				"limit": 0,
-			}
-		}};
-		let _ = self.keepalive_channel.send(Status::SendMessage(msg));
 	}
 }
 
@@ -538,13 +518,56 @@ impl Drop for Connection {
 	}
 }
 
+/// The message which sets the client's presence.
+pub(crate) fn presence_message(game: Option<Game>, status: OnlineStatus, afk: bool) -> serde_json::Value {
+	let status = match status {
+		OnlineStatus::Offline => OnlineStatus::Invisible,
+		other => other,
+	};
+	let game = match game {
+		Some(Game {
+			kind: GameType::Streaming,
+			url: Some(url),
+			name,
+		}) => json! {{ "type": GameType::Streaming, "url": url, "name": name }},
+		Some(game) => json! {{ "name": game.name, "type": GameType::Playing }},
+		None => json!(null),
+	};
+	json! {{
+		"op": 3,
+		"d": {
+			"afk": afk,
+			"since": 0,
+			"status": status,
+			"game": game,
+		}
+	}}
+}
+
+/// The message which requests all members of the servers `State` is
+/// missing members of, or `None` if it is missing none.
+pub(crate) fn request_members_message(state: &mut ::State) -> Option<serde_json::Value> {
+	if state.unknown_members() == 0 {
+		return None;
+	}
+	let servers = state.__download_members();
+	Some(json! {{
+		"op": 8,
+		"d": {
+			"guild_id": servers,
+			"query": "",
+			"limit": 0,
+		}
+	}})
+}
+
 #[inline]
 fn build_gateway_url(base: &str) -> Result<::websocket::client::request::Url> {
 	::websocket::client::request::Url::parse(&format!("{}?v={}", base, GATEWAY_VERSION))
 		.map_err(|_| Error::Other("Invalid gateway URL"))
 }
 
-fn keepalive(interval: u64, mut sender: Sender<WebSocketStream>, channel: mpsc::Receiver<Status>) {
+fn keepalive(interval: u64, mut sender: Sender<WsStream>, channel: mpsc::Receiver<Status>) {
 	let mut timer = ::Timer::new(interval);
 	let mut last_sequence = 0;
 
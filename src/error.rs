@@ -7,6 +7,8 @@ use serde_json::Value;
 use std::error::Error as StdError;
 use std::fmt::Display;
 use std::io::Error as IoError;
+#[cfg(feature = "async")]
+use tokio_tungstenite::tungstenite::Error as TungsteniteError;
 use websocket::result::WebSocketError;
 
 /// Discord API `Result` alias type.
@@ -23,6 +25,9 @@ pub enum Error {
 	Json(JsonError),
 	/// A `websocket` crate error
 	WebSocket(WebSocketError),
+	/// A `tungstenite` crate error, from the async gateway connection
+	#[cfg(feature = "async")]
+	Tungstenite(TungsteniteError),
 	/// A `std::io` module error
 	Io(IoError),
 	/// An error in the Opus library, with the function name and error code
@@ -33,9 +38,20 @@ pub enum Error {
 	/// A json decoding error, with a description and the offending value
 	Decode(&'static str, Value),
 	/// A generic non-success response from the REST API
-	Status(::hyper::status::StatusCode, Option<Value>),
-	/// A rate limit error, with how many milliseconds to wait before retrying
-	RateLimited(u64),
+	///
+	/// More fields may be added, so match on this variant using `..`.
+	#[non_exhaustive]
+	Status {
+		/// The HTTP status of the response
+		status: ::hyper::status::StatusCode,
+		/// The error Discord sent, if the response body was JSON
+		error: Option<ApiError>,
+		/// The request which failed
+		request: Option<RequestContext>,
+	},
+	/// A rate limit error, with how many milliseconds to wait before retrying,
+	/// and the failed request
+	RateLimited(u64, Option<RequestContext>),
 	/// A Discord protocol error, with a description
 	Protocol(&'static str),
 	/// A command execution failure, with a command name and output
@@ -46,17 +62,352 @@ pub enum Error {
 
 impl Error {
 	#[doc(hidden)]
-	pub fn from_response(response: ::hyper::client::Response) -> Error {
-		let status = response.status;
-		let value = ::serde_json::from_reader(response).ok();
+	pub fn from_response(response: ::hyper::client::Response, context: Option<RequestContext>) -> Error {
+		Error::from_body(response.status, response, context)
+	}
+
+	/// Convert a non-success status and the response body which came with it.
+	pub(crate) fn from_body<R: ::std::io::Read>(
+		status: ::hyper::status::StatusCode,
+		body: R,
+		context: Option<RequestContext>,
+	) -> Error {
+		let value = ::serde_json::from_reader(body).ok();
 		if status == ::hyper::status::StatusCode::TooManyRequests {
 			if let Some(Value::Object(ref map)) = value {
 				if let Some(delay) = map.get("retry_after").and_then(|v| v.as_u64()) {
-					return Error::RateLimited(delay);
+					return Error::RateLimited(delay, context);
 				}
 			}
 		}
-		Error::Status(status, value)
+		Error::Status {
+			status: status,
+			error: value.map(ApiError::decode),
+			request: context,
+		}
+	}
+
+	/// Get the error Discord sent with a non-success response, if any.
+	pub fn api_error(&self) -> Option<&ApiError> {
+		match *self {
+			Error::Status { error: Some(ref error), .. } => Some(error),
+			_ => None,
+		}
+	}
+
+	/// Get the request which failed, for errors from the REST API.
+	pub fn request_context(&self) -> Option<&RequestContext> {
+		match *self {
+			Error::Status { request: Some(ref context), .. } | Error::RateLimited(_, Some(ref context)) => {
+				Some(context)
+			}
+			_ => None,
+		}
+	}
+
+	/// Whether the requested resource does not exist, such as a deleted
+	/// message or a user who has left the server.
+	pub fn is_not_found(&self) -> bool {
+		match *self {
+			Error::Status { status: ::hyper::status::StatusCode::NotFound, .. } => true,
+			_ => self.api_error().map_or(false, |e| e.code.is_unknown_resource()),
+		}
+	}
+
+	/// Whether the request failed because the current user lacks the
+	/// permissions, or access to the channel, that it requires.
+	pub fn is_missing_permissions(&self) -> bool {
+		match self.api_error().map(|e| e.code) {
+			Some(ApiErrorCode::MissingPermissions) | Some(ApiErrorCode::MissingAccess) => true,
+			_ => false,
+		}
+	}
+}
+
+/// The REST request which produced an error.
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+	/// The HTTP method, like `PATCH`
+	pub method: String,
+	/// The route relative to the API base, with IDs replaced, like
+	/// `/channels/:id/messages/:id`
+	pub route: String,
+	/// The path and query relative to the API base, with IDs kept but
+	/// webhook and interaction tokens replaced, like `/webhooks/1/:token`
+	pub path: String,
+	/// Response headers useful for diagnosis, such as `X-Request-Id`,
+	/// `CF-Ray` and the rate limit headers
+	pub headers: Vec<(String, String)>,
+}
+
+/// Response headers kept in a `RequestContext`.
+const CONTEXT_HEADERS: &'static [&'static str] = &[
+	"X-Request-Id",
+	"CF-Ray",
+	"Via",
+	"Retry-After",
+	"X-RateLimit-Bucket",
+	"X-RateLimit-Scope",
+	"X-RateLimit-Global",
+	"X-RateLimit-Limit",
+	"X-RateLimit-Remaining",
+	"X-RateLimit-Reset-After",
+];
+
+impl RequestContext {
+	#[doc(hidden)]
+	pub fn new(method: &str, url: &str, headers: &::hyper::header::Headers) -> RequestContext {
+		RequestContext {
+			method: method.to_uppercase(),
+			route: ::ratelimit::route_template(url),
+			path: ::ratelimit::redacted_path(url),
+			headers: CONTEXT_HEADERS
+				.iter()
+				.filter_map(|&name| {
+					headers
+						.get_raw(name)
+						.and_then(|values| values.first())
+						.map(|value| (name.to_owned(), String::from_utf8_lossy(value).into_owned()))
+				})
+				.collect(),
+		}
+	}
+
+	/// Get the value of a kept response header, ignoring case.
+	pub fn header(&self, name: &str) -> Option<&str> {
+		self.headers
+			.iter()
+			.find(|&&(ref key, _)| key.eq_ignore_ascii_case(name))
+			.map(|&(_, ref value)| &value[..])
+	}
+}
+
+impl Display for RequestContext {
+	fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+		write!(f, "{} {}", self.method, self.route)?;
+		if let Some(id) = self.header("X-Request-Id").or_else(|| self.header("CF-Ray")) {
+			write!(f, ", request {}", id)?;
+		}
+		Ok(())
+	}
+}
+
+/// An error returned by the REST API.
+///
+/// See the [docs] for the meaning of each code.
+/// [docs]: https://discord.com/developers/docs/topics/opcodes-and-status-codes#json
+#[derive(Debug, Clone)]
+pub struct ApiError {
+	/// The error code, or `General` if none was sent
+	pub code: ApiErrorCode,
+	/// A description of the error, which may be empty
+	pub message: String,
+	/// Problems with individual fields of the request body, if it was invalid
+	pub errors: Vec<FieldError>,
+	/// The response body as it was received
+	pub raw: Value,
+}
+
+impl ApiError {
+	/// Decode the body of an error response, which need not be in Discord's
+	/// usual format.
+	pub fn decode(value: Value) -> ApiError {
+		let mut errors = Vec::new();
+		if let Some(fields) = value.get("errors") {
+			collect_field_errors(fields, String::new(), &mut errors);
+		}
+		ApiError {
+			code: ApiErrorCode::from_num(value.get("code").and_then(Value::as_u64).unwrap_or(0)),
+			message: value
+				.get("message")
+				.and_then(Value::as_str)
+				.unwrap_or("")
+				.to_owned(),
+			errors: errors,
+			raw: value,
+		}
+	}
+}
+
+/// A problem with a single field of a request body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+	/// The path to the field, like `embeds.0.title`
+	pub path: String,
+	/// A code such as `BASE_TYPE_MAX_LENGTH`
+	pub code: String,
+	/// A description of the problem
+	pub message: String,
+}
+
+/// Flatten Discord's nested `{"field": {"_errors": [...]}}` error objects.
+fn collect_field_errors(value: &Value, path: String, errors: &mut Vec<FieldError>) {
+	let map = match value.as_object() {
+		Some(map) => map,
+		None => return,
+	};
+	for (key, value) in map {
+		if key == "_errors" {
+			for error in value.as_array().into_iter().flat_map(|e| e) {
+				let field = |name| error.get(name).and_then(Value::as_str).unwrap_or("").to_owned();
+				errors.push(FieldError {
+					path: path.clone(),
+					code: field("code"),
+					message: field("message"),
+				});
+			}
+		} else if path.is_empty() {
+			collect_field_errors(value, key.clone(), errors);
+		} else {
+			collect_field_errors(value, format!("{}.{}", path, key), errors);
+		}
+	}
+}
+
+/// A JSON error code returned by the REST API.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum ApiErrorCode {
+	/// A general error, such as a malformed request
+	General,
+	/// Unknown account
+	UnknownAccount,
+	/// Unknown application
+	UnknownApplication,
+	/// Unknown channel
+	UnknownChannel,
+	/// Unknown server
+	UnknownServer,
+	/// Unknown integration
+	UnknownIntegration,
+	/// Unknown invite
+	UnknownInvite,
+	/// Unknown member
+	UnknownMember,
+	/// Unknown message
+	UnknownMessage,
+	/// Unknown permission overwrite
+	UnknownPermissionOverwrite,
+	/// Unknown role
+	UnknownRole,
+	/// Unknown token
+	UnknownToken,
+	/// Unknown user
+	UnknownUser,
+	/// Unknown emoji
+	UnknownEmoji,
+	/// Unknown webhook
+	UnknownWebhook,
+	/// Unknown ban
+	UnknownBan,
+	/// Unknown server template
+	UnknownTemplate,
+	/// Unknown sticker
+	UnknownSticker,
+	/// Unknown interaction, usually one responded to too late
+	UnknownInteraction,
+	/// Unknown application command
+	UnknownApplicationCommand,
+	/// Unknown stage instance
+	UnknownStageInstance,
+	/// Unknown scheduled event
+	UnknownScheduledEvent,
+	/// Bots cannot use this endpoint
+	BotsCannotUse,
+	/// Only bots can use this endpoint
+	OnlyBotsCanUse,
+	/// Maximum number of servers reached
+	MaxServers,
+	/// Maximum number of pins reached for the channel
+	MaxPins,
+	/// Maximum number of server roles reached
+	MaxRoles,
+	/// Maximum number of webhooks reached
+	MaxWebhooks,
+	/// Maximum number of reactions reached
+	MaxReactions,
+	/// Maximum number of server channels reached
+	MaxChannels,
+	/// Unauthorized; the token may be invalid
+	Unauthorized,
+	/// Request entity too large
+	RequestTooLarge,
+	/// The interaction has already been acknowledged
+	InteractionAlreadyAcknowledged,
+	/// Missing access, usually to the channel
+	MissingAccess,
+	/// Invalid account type
+	InvalidAccountType,
+	/// Cannot edit a message authored by another user
+	CannotEditOtherUsersMessage,
+	/// Cannot send an empty message
+	EmptyMessage,
+	/// Cannot send messages to this user
+	CannotMessageUser,
+	/// Missing permissions
+	MissingPermissions,
+	/// Invalid OAuth2 access token
+	InvalidOAuthToken,
+	/// A message was too old to bulk delete
+	MessageTooOldToBulkDelete,
+	/// Invalid form body; see `ApiError::errors`
+	InvalidFormBody,
+	/// A code not covered by the above
+	Unknown(u64),
+}
+
+serial_numbers! { ApiErrorCode(Unknown);
+	General, 0;
+	UnknownAccount, 10001;
+	UnknownApplication, 10002;
+	UnknownChannel, 10003;
+	UnknownServer, 10004;
+	UnknownIntegration, 10005;
+	UnknownInvite, 10006;
+	UnknownMember, 10007;
+	UnknownMessage, 10008;
+	UnknownPermissionOverwrite, 10009;
+	UnknownRole, 10011;
+	UnknownToken, 10012;
+	UnknownUser, 10013;
+	UnknownEmoji, 10014;
+	UnknownWebhook, 10015;
+	UnknownBan, 10026;
+	UnknownTemplate, 10057;
+	UnknownSticker, 10060;
+	UnknownInteraction, 10062;
+	UnknownApplicationCommand, 10063;
+	UnknownStageInstance, 10067;
+	UnknownScheduledEvent, 10070;
+	BotsCannotUse, 20001;
+	OnlyBotsCanUse, 20002;
+	MaxServers, 30001;
+	MaxPins, 30003;
+	MaxRoles, 30005;
+	MaxWebhooks, 30007;
+	MaxReactions, 30010;
+	MaxChannels, 30013;
+	Unauthorized, 40001;
+	RequestTooLarge, 40005;
+	InteractionAlreadyAcknowledged, 40060;
+	MissingAccess, 50001;
+	InvalidAccountType, 50002;
+	CannotEditOtherUsersMessage, 50005;
+	EmptyMessage, 50006;
+	CannotMessageUser, 50007;
+	MissingPermissions, 50013;
+	InvalidOAuthToken, 50025;
+	MessageTooOldToBulkDelete, 50034;
+	InvalidFormBody, 50035;
+}
+
+impl ApiErrorCode {
+	/// Whether this code reports that some resource does not exist, as codes
+	/// from 10001 to 10999 do.
+	pub fn is_unknown_resource(&self) -> bool {
+		match self.num() {
+			10001..=10999 => true,
+			_ => false,
+		}
 	}
 }
 
@@ -90,6 +441,13 @@ impl From<WebSocketError> for Error {
 	}
 }
 
+#[cfg(feature = "async")]
+impl From<TungsteniteError> for Error {
+	fn from(err: TungsteniteError) -> Error {
+		Error::Tungstenite(err)
+	}
+}
+
 #[cfg(feature = "voice")]
 impl From<OpusError> for Error {
 	fn from(err: OpusError) -> Error {
@@ -105,10 +463,27 @@ impl Display for Error {
 			Error::Chrono(ref inner) => inner.fmt(f),
 			Error::Json(ref inner) => inner.fmt(f),
 			Error::WebSocket(ref inner) => inner.fmt(f),
+			#[cfg(feature = "async")]
+			Error::Tungstenite(ref inner) => inner.fmt(f),
 			Error::Io(ref inner) => inner.fmt(f),
 			#[cfg(feature = "voice")]
 			Error::Opus(ref inner) => inner.fmt(f),
 			Error::Command(cmd, _) => write!(f, "Command failed: {}", cmd),
+			Error::Status { status, ref error, request: ref context, .. } => {
+				match *error {
+					Some(ref error) if !error.message.is_empty() => {
+						write!(f, "{}: {} ({})", status, error.message, error.code.num())?
+					}
+					_ => f.write_str(self.description())?,
+				}
+				match *context {
+					Some(ref context) => write!(f, " [{}]", context),
+					None => Ok(()),
+				}
+			}
+			Error::RateLimited(delay, Some(ref context)) => {
+				write!(f, "Rate limited for {}ms [{}]", delay, context)
+			}
 			_ => f.write_str(self.description()),
 		}
 	}
@@ -122,15 +497,17 @@ impl StdError for Error {
 			Error::Chrono(ref inner) => inner.description(),
 			Error::Json(ref inner) => inner.description(),
 			Error::WebSocket(ref inner) => inner.description(),
+			#[cfg(feature = "async")]
+			Error::Tungstenite(ref inner) => inner.description(),
 			Error::Io(ref inner) => inner.description(),
 			#[cfg(feature = "voice")]
 			Error::Opus(ref inner) => inner.description(),
 			Error::Closed(_, _) => "Connection closed",
 			Error::Decode(msg, _) | Error::Protocol(msg) | Error::Other(msg) => msg,
-			Error::Status(status, _) => status
+			Error::Status { status, .. } => status
 				.canonical_reason()
 				.unwrap_or("Unknown bad HTTP status"),
-			Error::RateLimited(_) => "Rate limited",
+			Error::RateLimited(_, _) => "Rate limited",
 			Error::Command(_, _) => "Command failed",
 		}
 	}
@@ -141,6 +518,8 @@ impl StdError for Error {
 			Error::Chrono(ref inner) => Some(inner),
 			Error::Json(ref inner) => Some(inner),
 			Error::WebSocket(ref inner) => Some(inner),
+			#[cfg(feature = "async")]
+			Error::Tungstenite(ref inner) => Some(inner),
 			Error::Io(ref inner) => Some(inner),
 			#[cfg(feature = "voice")]
 			Error::Opus(ref inner) => Some(inner),
@@ -148,3 +527,71 @@ impl StdError for Error {
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use model::{ChannelId, MessageId, WebhookId};
+	use test_support::{mock_discord, mock_server};
+	use DiscordBuilder;
+
+	#[test]
+	fn api_error() {
+		let error = Error::Status {
+			status: ::hyper::status::StatusCode::BadRequest,
+			error: Some(ApiError::decode(json!({
+				"code": 50035,
+				"message": "Invalid Form Body",
+				"errors": {
+					"content": {"_errors": [{"code": "BASE_TYPE_MAX_LENGTH", "message": "Too long"}]},
+					"embeds": {"0": {"title": {"_errors": [{"code": "BASE_TYPE_REQUIRED", "message": "Required"}]}}},
+				}
+			}))),
+			request: None,
+		};
+		let api = error.api_error().unwrap();
+		assert_eq!(api.code, ApiErrorCode::InvalidFormBody);
+		assert_eq!(api.errors.len(), 2);
+		assert_eq!(api.errors[0].path, "content");
+		assert_eq!(api.errors[1].path, "embeds.0.title");
+		assert_eq!(api.errors[1].code, "BASE_TYPE_REQUIRED");
+		assert_eq!(error.to_string(), "400 Bad Request: Invalid Form Body (50035)");
+		assert!(!error.is_not_found());
+
+		let error = Error::Status {
+			status: ::hyper::status::StatusCode::Forbidden,
+			error: Some(ApiError::decode(json!({"code": 50013, "message": "Missing Permissions"}))),
+			request: None,
+		};
+		assert!(error.is_missing_permissions());
+		let error = Error::Status {
+			status: ::hyper::status::StatusCode::NotFound,
+			error: Some(ApiError::decode(json!({"code": 10008, "message": "Unknown Message"}))),
+			request: None,
+		};
+		assert!(error.is_not_found());
+		assert_eq!(error.api_error().unwrap().code, ApiErrorCode::UnknownMessage);
+	}
+
+	#[test]
+	fn request_context() {
+		let (base, _requests) = mock_server("404 Not Found", r#"{"message":"Unknown Message","code":10008}"#);
+		let err = mock_discord(&base).get_message(ChannelId(3), MessageId(2)).unwrap_err();
+		assert!(err.is_not_found());
+		let context = err.request_context().unwrap();
+		assert_eq!(context.method, "GET");
+		assert_eq!(context.route, "/channels/:id/messages/:id");
+		assert_eq!(context.path, "/channels/3/messages/2");
+		assert_eq!(err.to_string(), "404 Not Found: Unknown Message (10008) [GET /channels/:id/messages/:id]");
+
+		let (base, _requests) = mock_server("401 Unauthorized", r#"{"message":"Invalid Webhook Token","code":50027}"#);
+		let webhook = DiscordBuilder::new()
+			.with_api_base(&base)
+			.with_transport(::hyper::net::HttpConnector)
+			.webhook_client(WebhookId(7), "s3cret")
+			.unwrap();
+		let err = webhook.get().unwrap_err();
+		assert_eq!(err.request_context().unwrap().path, "/webhooks/7/:token");
+		assert!(!format!("{:?} {}", err, err).contains("s3cret"));
+	}
+}
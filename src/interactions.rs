@@ -0,0 +1,216 @@
+//! Receiving interactions over HTTP instead of the gateway.
+//!
+//! An application may set an "interactions endpoint URL" in the developer
+//! portal, after which Discord delivers interactions to it as HTTP POST
+//! requests rather than as `InteractionCreate` events. Each request is signed
+//! using the application's public key, and must be answered with the initial
+//! response to the interaction.
+//!
+//! ```ignore
+//! let server = InteractionsServer::new(PUBLIC_KEY, |interaction, response| {
+//!     response.message(|m| m.content("Pong!"))
+//! })?;
+//! // Runs until the process exits
+//! let _listening = server.listen("0.0.0.0:8080")?;
+//! ```
+
+use std::convert::TryFrom;
+use std::io::Read;
+use std::net::ToSocketAddrs;
+
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use hyper::header::ContentType;
+use hyper::method::Method;
+use hyper::server::{Handler, Listening, Request, Response, Server};
+use hyper::status::StatusCode;
+use serde_json;
+
+use builders::CreateInteractionResponse;
+use model::{Interaction, InteractionType};
+use {Error, Object, Result};
+
+/// Interaction payloads larger than this are rejected.
+const MAX_BODY: u64 = 1 << 20;
+
+/// A blocking HTTP server which verifies and dispatches interactions.
+///
+/// The handler is called with each decoded `Interaction` and a blank
+/// `CreateInteractionResponse`, and returns the initial response to send.
+/// Pings are answered without calling the handler. Requests without a valid
+/// signature are rejected with `401 Unauthorized`, as Discord requires.
+pub struct InteractionsServer<F> {
+	public_key: PublicKey,
+	handler: F,
+}
+
+impl<F> InteractionsServer<F>
+where
+	F: Fn(Interaction, CreateInteractionResponse) -> CreateInteractionResponse + Send + Sync + 'static,
+{
+	/// Create a server from the application's hex-encoded public key, as
+	/// shown in the developer portal, and a handler.
+	pub fn new(public_key: &str, handler: F) -> Result<InteractionsServer<F>> {
+		let public_key = decode_hex(public_key)
+			.and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+			.ok_or(Error::Other("Invalid interactions public key"))?;
+		Ok(InteractionsServer {
+			public_key: public_key,
+			handler: handler,
+		})
+	}
+
+	/// Start serving requests on the given address on background threads.
+	///
+	/// Dropping the returned `Listening` blocks until the server stops, which
+	/// allows a bot's `main` to simply hold on to it.
+	pub fn listen<A: ToSocketAddrs>(self, addr: A) -> Result<Listening> {
+		Ok(Server::http(addr)?.handle(self)?)
+	}
+
+	/// Check that `body` was signed by Discord, given the values of the
+	/// `X-Signature-Ed25519` and `X-Signature-Timestamp` headers.
+	///
+	/// Useful when receiving interactions through another HTTP server.
+	pub fn verify(&self, signature: &str, timestamp: &str, body: &[u8]) -> bool {
+		let signature = match decode_hex(signature).and_then(|bytes| Signature::try_from(&bytes[..]).ok()) {
+			Some(signature) => signature,
+			None => return false,
+		};
+		let mut message = Vec::with_capacity(timestamp.len() + body.len());
+		message.extend_from_slice(timestamp.as_bytes());
+		message.extend_from_slice(body);
+		self.public_key.verify(&message, &signature).is_ok()
+	}
+
+	/// Decode a verified request body and produce the response to send.
+	///
+	/// Useful when receiving interactions through another HTTP server.
+	pub fn dispatch(&self, body: &[u8]) -> Result<Object> {
+		let interaction = Interaction::decode(serde_json::from_slice(body)?)?;
+		if interaction.kind == InteractionType::Ping {
+			Ok(CreateInteractionResponse::__build(|r| r.pong()))
+		} else {
+			Ok(CreateInteractionResponse::__build(|r| (self.handler)(interaction, r)))
+		}
+	}
+
+	fn respond(&self, request: &mut Request) -> (StatusCode, Option<Vec<u8>>) {
+		if request.method != Method::Post {
+			return (StatusCode::MethodNotAllowed, None);
+		}
+		let signature = header(request, "X-Signature-Ed25519");
+		let timestamp = header(request, "X-Signature-Timestamp");
+		let mut body = Vec::new();
+		if request.by_ref().take(MAX_BODY).read_to_end(&mut body).is_err() {
+			return (StatusCode::BadRequest, None);
+		}
+		match (signature, timestamp) {
+			(Some(ref signature), Some(ref timestamp)) if self.verify(signature, timestamp, &body) => {}
+			_ => return (StatusCode::Unauthorized, None),
+		}
+		match self.dispatch(&body).and_then(|response| Ok(serde_json::to_vec(&response)?)) {
+			Ok(response) => (StatusCode::Ok, Some(response)),
+			Err(err) => {
+				warn!("Failed to handle interaction: {:?}", err);
+				(StatusCode::BadRequest, None)
+			}
+		}
+	}
+}
+
+impl<F> Handler for InteractionsServer<F>
+where
+	F: Fn(Interaction, CreateInteractionResponse) -> CreateInteractionResponse + Send + Sync + 'static,
+{
+	fn handle(&self, mut request: Request, mut response: Response) {
+		let (status, body) = self.respond(&mut request);
+		*response.status_mut() = status;
+		let result = match body {
+			Some(body) => {
+				response.headers_mut().set(ContentType::json());
+				response.send(&body)
+			}
+			None => response.send(b""),
+		};
+		if let Err(err) = result {
+			debug!("Failed to send interaction response: {:?}", err);
+		}
+	}
+}
+
+fn header(request: &Request, name: &str) -> Option<String> {
+	request
+		.headers
+		.get_raw(name)
+		.and_then(|values| values.first())
+		.and_then(|value| String::from_utf8(value.clone()).ok())
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+	if hex.len() % 2 != 0 {
+		return None;
+	}
+	(0..hex.len())
+		.step_by(2)
+		.map(|i| hex.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+		.collect()
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use ed25519_dalek::{ExpandedSecretKey, SecretKey};
+	use std::io::{Read, Write};
+	use std::net::TcpStream;
+
+	fn hex(bytes: &[u8]) -> String {
+		bytes.iter().map(|b| format!("{:02x}", b)).collect()
+	}
+
+	fn post(addr: ::std::net::SocketAddr, signature: &str, body: &str) -> String {
+		let mut stream = TcpStream::connect(addr).unwrap();
+		write!(
+			stream,
+			"POST / HTTP/1.1\r\nHost: localhost\r\nX-Signature-Ed25519: {}\r\nX-Signature-Timestamp: 1600000000\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+			signature,
+			body.len(),
+			body
+		)
+		.unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		response
+	}
+
+	#[test]
+	fn verified_interactions() {
+		let secret = SecretKey::from_bytes(&[7; 32]).unwrap();
+		let public = PublicKey::from(&secret);
+		let sign = |body: &str| {
+			let message = format!("1600000000{}", body);
+			hex(&ExpandedSecretKey::from(&secret).sign(message.as_bytes(), &public).to_bytes())
+		};
+
+		let server = InteractionsServer::new(&hex(public.as_bytes()), |interaction, response| {
+			assert_eq!(interaction.token, "tok");
+			response.message(|m| m.content("hello"))
+		})
+		.unwrap();
+		let mut listening = server.listen("127.0.0.1:0").unwrap();
+		let addr = listening.socket;
+
+		let ping = r#"{"id":"8","application_id":"1","type":1,"token":"tok","version":1}"#;
+		let response = post(addr, &sign(ping), ping);
+		assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+		assert!(response.ends_with(r#"{"type":1}"#));
+
+		let command = r#"{"id":"8","application_id":"1","type":2,"token":"tok","version":1,"data":{"id":"10","name":"hi","type":1}}"#;
+		let response = post(addr, &sign(command), command);
+		assert!(response.ends_with(r#"{"data":{"content":"hello"},"type":4}"#));
+
+		let response = post(addr, &sign(ping), command);
+		assert!(response.starts_with("HTTP/1.1 401 Unauthorized\r\n"));
+
+		listening.close().unwrap();
+	}
+}
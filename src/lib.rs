@@ -43,6 +43,28 @@ extern crate serde_json;
 extern crate bitflags;
 #[macro_use]
 extern crate log;
+#[cfg(feature = "interactions")]
+extern crate ed25519_dalek;
+#[cfg(feature = "async")]
+extern crate bytes;
+#[cfg(feature = "async")]
+extern crate futures_core;
+#[cfg(feature = "async")]
+extern crate futures_util;
+#[cfg(feature = "async")]
+extern crate http_body_util;
+#[cfg(feature = "async")]
+extern crate hyper1;
+#[cfg(feature = "async")]
+extern crate hyper_tls;
+#[cfg(feature = "async")]
+extern crate hyper_util;
+#[cfg(feature = "async")]
+extern crate tokio;
+#[cfg(feature = "async")]
+extern crate tokio_tungstenite;
+#[cfg(feature = "async")]
+extern crate tower_service;
 #[cfg(feature = "voice")]
 extern crate byteorder;
 #[cfg(feature = "voice")]
@@ -51,35 +73,44 @@ extern crate opus;
 extern crate sodiumoxide;
 
 use std::collections::BTreeMap;
+use std::sync::Arc;
 use std::time;
 
+use chrono::{DateTime, FixedOffset, Utc};
+
 type Object = serde_json::Map<String, serde_json::Value>;
 
 mod connection;
-mod error;
+mod proxy;
 mod ratelimit;
+mod retry;
 mod state;
+#[cfg(test)]
+mod test_support;
+mod transport;
 #[cfg(feature = "voice")]
 pub mod voice;
-
-macro_rules! cdn_concat {
-	($e:expr) => {
-		// Out of everything, only the CDN still uses the old domain.
-		concat!("https://cdn.discordapp.com", $e)
-	};
-}
+#[cfg(feature = "async")]
+pub mod aio;
+#[cfg(feature = "interactions")]
+pub mod interactions;
 
 #[macro_use]
 mod serial;
 pub mod builders;
+pub mod cdn;
+mod error;
 pub mod model;
 
 use builders::*;
 pub use connection::Connection;
-pub use error::{Error, Result};
+pub use error::{ApiError, ApiErrorCode, Error, FieldError, RequestContext, Result};
 use model::*;
+pub use proxy::Proxy;
 use ratelimit::RateLimits;
+pub use retry::RetryPolicy;
 pub use state::{ChannelRef, State};
+pub use transport::Transport;
 
 const USER_AGENT: &'static str = concat!(
 	"DiscordBot (https://github.com/SpaceManiac/discord-rs, ",
@@ -99,23 +130,28 @@ macro_rules! status_concat {
 
 macro_rules! request {
 	($self_:ident, $method:ident($body:expr), $url:expr, $($rest:tt)*) => {{
-		let path = format!(api_concat!($url), $($rest)*);
-		$self_.request(&path, || $self_.client.$method(&path).body(&$body))?
+		let path = format!(concat!("{}", $url), $self_.config.api_base, $($rest)*);
+		$self_.request(stringify!($method), &path, || $self_.client.$method(&path).body(&$body))?
 	}};
 	($self_:ident, $method:ident, $url:expr, $($rest:tt)*) => {{
-		let path = format!(api_concat!($url), $($rest)*);
-		$self_.request(&path, || $self_.client.$method(&path))?
+		let path = format!(concat!("{}", $url), $self_.config.api_base, $($rest)*);
+		$self_.request(stringify!($method), &path, || $self_.client.$method(&path))?
 	}};
 	($self_:ident, $method:ident($body:expr), $url:expr) => {{
-		let path = api_concat!($url);
-		$self_.request(path, || $self_.client.$method(path).body(&$body))?
+		let path = format!(concat!("{}", $url), $self_.config.api_base);
+		$self_.request(stringify!($method), &path, || $self_.client.$method(&path).body(&$body))?
 	}};
 	($self_:ident, $method:ident, $url:expr) => {{
-		let path = api_concat!($url);
-		$self_.request(path, || $self_.client.$method(path))?
+		let path = format!(concat!("{}", $url), $self_.config.api_base);
+		$self_.request(stringify!($method), &path, || $self_.client.$method(&path))?
 	}};
 }
 
+mod pagination;
+mod webhook;
+pub use pagination::{MessageHistory, Paginated};
+pub use webhook::WebhookClient;
+
 /// Client for the Discord REST API.
 ///
 /// Log in to the API with a user's email and password using `new()`. Call
@@ -123,48 +159,175 @@ macro_rules! request {
 /// use `logout()` to invalidate the token when done. Other methods manipulate
 /// the Discord REST API.
 pub struct Discord {
-	rate_limits: RateLimits,
-	client: hyper::Client,
+	rate_limits: Arc<RateLimits>,
+	client: Arc<hyper::Client>,
 	token: String,
+	config: DiscordBuilder,
+	reason: Option<String>,
 }
 
-fn tls_client() -> hyper::Client {
-	let tls = hyper_native_tls::NativeTlsClient::new().expect("Error initializing NativeTlsClient");
-	let connector = hyper::net::HttpsConnector::new(tls);
-	hyper::Client::with_connector(connector)
+/// The `X-Audit-Log-Reason` header, holding an already URL-encoded reason.
+#[derive(Clone, Debug)]
+struct AuditLogReason(String);
+
+impl hyper::header::Header for AuditLogReason {
+	fn header_name() -> &'static str {
+		"X-Audit-Log-Reason"
+	}
+
+	fn parse_header(raw: &[Vec<u8>]) -> hyper::Result<AuditLogReason> {
+		hyper::header::parsing::from_one_raw_str(raw).map(AuditLogReason)
+	}
 }
 
-impl Discord {
+impl hyper::header::HeaderFormat for AuditLogReason {
+	fn fmt_header(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+/// Percent-encode everything but unreserved URL characters.
+fn percent_encode(text: &str) -> String {
+	use std::fmt::Write;
+	let mut result = String::with_capacity(text.len());
+	for &byte in text.as_bytes() {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => result.push(byte as char),
+			_ => {
+				let _ = write!(result, "%{:02X}", byte);
+			}
+		}
+	}
+	result
+}
+
+/// Append the query parameters which are present to a URL, percent-encoding
+/// their values.
+fn with_query(mut url: String, params: &[(&str, Option<String>)]) -> String {
+	let mut separator = '?';
+	for &(key, ref value) in params {
+		if let Some(ref value) = *value {
+			url.push(separator);
+			url.push_str(key);
+			url.push('=');
+			url.push_str(&percent_encode(value));
+			separator = '&';
+		}
+	}
+	url
+}
+
+/// Configuration from which a `Discord` client is built.
+///
+/// Allows the REST API base URL and the HTTP transport to be replaced, and a
+/// proxy to be configured, for example in order to direct all requests at a
+/// local mock server:
+///
+/// ```ignore
+/// let discord = DiscordBuilder::new()
+///     .with_api_base("http://127.0.0.1:8080/api/v6")
+///     .with_transport(hyper::net::HttpConnector)
+///     .from_bot_token(&token)?;
+/// ```
+#[derive(Clone)]
+pub struct DiscordBuilder {
+	api_base: String,
+	status_base: String,
+	cdn_base: String,
+	transport: Option<Arc<dyn Transport>>,
+	proxy: Option<Proxy>,
+	retry_policy: RetryPolicy,
+}
+
+impl DiscordBuilder {
+	/// Create a configuration using the official API and the default transport.
+	pub fn new() -> Self {
+		DiscordBuilder {
+			api_base: api_concat!("").to_owned(),
+			status_base: status_concat!("").to_owned(),
+			cdn_base: cdn::CDN_BASE.to_owned(),
+			transport: None,
+			proxy: None,
+			retry_policy: RetryPolicy::default(),
+		}
+	}
+
+	/// Set the base URL to which REST API paths are appended.
+	///
+	/// The base should not end with a slash. Defaults to the Discord API v6.
+	pub fn with_api_base(&mut self, api_base: &str) -> &mut Self {
+		self.api_base = api_base.trim_end_matches('/').to_owned();
+		self
+	}
+
+	/// Set the base URL of the status page API, used by the status methods.
+	///
+	/// The base should not end with a slash. Defaults to the status page v2.
+	pub fn with_status_base(&mut self, status_base: &str) -> &mut Self {
+		self.status_base = status_base.trim_end_matches('/').to_owned();
+		self
+	}
+
+	/// Set the base URL of the CDN, used by `Discord::get_user_avatar_url`
+	/// and `Discord::cdn_url`.
+	///
+	/// The base should not end with a slash. Defaults to `cdn::CDN_BASE`.
+	pub fn with_cdn_base(&mut self, cdn_base: &str) -> &mut Self {
+		self.cdn_base = cdn_base.trim_end_matches('/').to_owned();
+		self
+	}
+
+	/// Set the transport over which all REST requests, including logins,
+	/// file uploads and status page requests, are made.
+	///
+	/// A custom transport cannot be combined with `with_proxy`.
+	pub fn with_transport<T: Transport + 'static>(&mut self, transport: T) -> &mut Self {
+		self.transport = Some(Arc::new(transport));
+		self
+	}
+
+	/// Make REST requests and gateway and voice websocket connections
+	/// through the given proxy.
+	///
+	/// A proxy cannot be combined with `with_transport`, since the transport
+	/// would make REST requests without it. Building a client with both set
+	/// fails.
+	pub fn with_proxy(&mut self, proxy: Proxy) -> &mut Self {
+		self.proxy = Some(proxy);
+		self
+	}
+
+	/// Set when failed REST requests are retried. See `RetryPolicy` for the
+	/// defaults.
+	pub fn with_retry_policy(&mut self, retry_policy: RetryPolicy) -> &mut Self {
+		self.retry_policy = retry_policy;
+		self
+	}
+
+	/// Log in as a bot account using the given authentication token.
+	///
+	/// The token will automatically be prefixed with "Bot ".
+	pub fn from_bot_token(&self, token: &str) -> Result<Discord> {
+		self.build(format!("Bot {}", token.trim()))
+	}
+
+	/// Log in as a user account using the given authentication token.
+	pub fn from_user_token(&self, token: &str) -> Result<Discord> {
+		self.build(token.trim().to_owned())
+	}
+
+	/// Create a client for a single webhook, authenticated by its token alone.
+	pub fn webhook_client(&self, webhook: WebhookId, token: &str) -> Result<WebhookClient> {
+		WebhookClient::from_config(self, webhook, token)
+	}
+
 	/// Log in to the Discord Rest API and acquire a token.
 	#[deprecated(note = "Login automation is not recommended. Use `from_user_token` instead.")]
-	pub fn new(email: &str, password: &str) -> Result<Discord> {
+	pub fn login(&self, email: &str, password: &str) -> Result<Discord> {
 		let mut map = BTreeMap::new();
 		map.insert("email", email);
 		map.insert("password", password);
-
-		let client = tls_client();
-		let response = check_status(
-			client
-				.post(api_concat!("/auth/login"))
-				.header(hyper::header::ContentType::json())
-				.header(hyper::header::UserAgent(USER_AGENT.to_owned()))
-				.body(&serde_json::to_string(&map)?)
-				.send(),
-		)?;
-		let mut json: BTreeMap<String, String> = serde_json::from_reader(response)?;
-		let token = match json.remove("token") {
-			Some(token) => token,
-			None => {
-				return Err(Error::Protocol(
-					"Response missing \"token\" in Discord::new()",
-				))
-			}
-		};
-		Ok(Discord {
-			rate_limits: RateLimits::default(),
-			client: client,
-			token: token,
-		})
+		self.login_with(&map, None)
 	}
 
 	/// Log in to the Discord Rest API, possibly using a cached login token.
@@ -174,7 +337,8 @@ impl Discord {
 	/// password was specified, an error is returned.
 	#[deprecated(note = "Login automation is not recommended. Use `from_user_token` instead.")]
 	#[allow(deprecated)]
-	pub fn new_cache<P: AsRef<std::path::Path>>(
+	pub fn login_cached<P: AsRef<std::path::Path>>(
+		&self,
 		path: P,
 		email: &str,
 		password: Option<&str>,
@@ -203,33 +367,9 @@ impl Discord {
 			if let Some(password) = password {
 				map.insert("password", password);
 			}
-
-			let client = tls_client();
-			let response = check_status(
-				client
-					.post(api_concat!("/auth/login"))
-					.header(hyper::header::ContentType::json())
-					.header(hyper::header::UserAgent(USER_AGENT.to_owned()))
-					.header(hyper::header::Authorization(initial_token.clone()))
-					.body(&serde_json::to_string(&map)?)
-					.send(),
-			)?;
-			let mut json: BTreeMap<String, String> = serde_json::from_reader(response)?;
-			let token = match json.remove("token") {
-				Some(token) => token,
-				None => {
-					return Err(Error::Protocol(
-						"Response missing \"token\" in Discord::new()",
-					))
-				}
-			};
-			Discord {
-				rate_limits: RateLimits::default(),
-				client: client,
-				token: token,
-			}
+			self.login_with(&map, Some(initial_token))?
 		} else if let Some(password) = password {
-			Discord::new(email, password)?
+			self.login(email, password)?
 		} else {
 			return Err(Error::Other(
 				"No password was specified and no cached token was found",
@@ -258,24 +398,172 @@ impl Discord {
 		Ok(discord)
 	}
 
-	fn from_token_raw(token: String) -> Discord {
-		Discord {
-			rate_limits: RateLimits::default(),
-			client: tls_client(),
+	fn login_with(&self, map: &BTreeMap<&str, &str>, token: Option<&str>) -> Result<Discord> {
+		let client = self.client()?;
+		let url = format!("{}/auth/login", self.api_base);
+		let body = serde_json::to_string(map)?;
+		let mut request = client
+			.post(&url)
+			.header(hyper::header::ContentType::json())
+			.header(hyper::header::UserAgent(USER_AGENT.to_owned()))
+			.body(&body);
+		if let Some(token) = token {
+			request = request.header(hyper::header::Authorization(token.to_owned()));
+		}
+		let response = check_request_status("post", &url, request.send())?;
+		let mut json: BTreeMap<String, String> = serde_json::from_reader(response)?;
+		let token = match json.remove("token") {
+			Some(token) => token,
+			None => {
+				return Err(Error::Protocol(
+					"Response missing \"token\" in Discord::new()",
+				))
+			}
+		};
+		Ok(Discord {
+			rate_limits: Arc::new(RateLimits::default()),
+			client: Arc::new(client),
 			token: token,
+			config: self.clone(),
+			reason: None,
+		})
+	}
+
+	/// Retrieves the current unresolved incidents from the status page.
+	pub fn get_unresolved_incidents(&self) -> Result<Vec<Incident>> {
+		let mut json = self.get_status("/incidents/unresolved.json")?;
+		match json.remove("incidents") {
+			Some(incidents) => decode_array(incidents, Incident::decode),
+			None => Ok(vec![]),
+		}
+	}
+
+	/// Retrieves the active maintenances from the status page.
+	pub fn get_active_maintenances(&self) -> Result<Vec<Maintenance>> {
+		let mut json = self.get_status("/scheduled-maintenances/active.json")?;
+		match json.remove("scheduled_maintenances") {
+			Some(scheduled_maintenances) => decode_array(scheduled_maintenances, Maintenance::decode),
+			None => Ok(vec![]),
 		}
 	}
 
+	/// Retrieves the upcoming maintenances from the status page.
+	pub fn get_upcoming_maintenances(&self) -> Result<Vec<Maintenance>> {
+		let mut json = self.get_status("/scheduled-maintenances/upcoming.json")?;
+		match json.remove("scheduled_maintenances") {
+			Some(scheduled_maintenances) => decode_array(scheduled_maintenances, Maintenance::decode),
+			None => Ok(vec![]),
+		}
+	}
+
+	fn get_status(&self, path: &str) -> Result<Object> {
+		let client = self.client()?;
+		let url = format!("{}{}", self.status_base, path);
+		let response = check_request_status("get", &url, retry(|| client.get(&url)))?;
+		Ok(serde_json::from_reader(response)?)
+	}
+
+	fn client(&self) -> Result<hyper::Client> {
+		Ok(hyper::Client::with_connector(transport::Connector(self.transport()?)))
+	}
+
+	fn transport(&self) -> Result<Arc<dyn Transport>> {
+		match (&self.transport, &self.proxy) {
+			(&Some(_), &Some(_)) => Err(Error::Other("A custom transport cannot be combined with a proxy")),
+			(&Some(ref transport), &None) => Ok(transport.clone()),
+			(&None, &Some(ref proxy)) => Ok(Arc::new(proxy::ProxyConnector::new(proxy.clone())?)),
+			(&None, &None) => transport::default_transport(),
+		}
+	}
+
+	fn build(&self, token: String) -> Result<Discord> {
+		Ok(Discord {
+			rate_limits: Arc::new(RateLimits::default()),
+			client: Arc::new(self.client()?),
+			token: token,
+			config: self.clone(),
+			reason: None,
+		})
+	}
+}
+
+impl Default for DiscordBuilder {
+	fn default() -> Self {
+		DiscordBuilder::new()
+	}
+}
+
+impl Discord {
+	/// Log in to the Discord Rest API and acquire a token.
+	#[deprecated(note = "Login automation is not recommended. Use `from_user_token` instead.")]
+	#[allow(deprecated)]
+	pub fn new(email: &str, password: &str) -> Result<Discord> {
+		DiscordBuilder::new().login(email, password)
+	}
+
+	/// Log in to the Discord Rest API, possibly using a cached login token.
+	///
+	/// Cached login tokens are keyed to the email address and will be read from
+	/// and written to the specified path. If no cached token was found and no
+	/// password was specified, an error is returned.
+	#[deprecated(note = "Login automation is not recommended. Use `from_user_token` instead.")]
+	#[allow(deprecated)]
+	pub fn new_cache<P: AsRef<std::path::Path>>(
+		path: P,
+		email: &str,
+		password: Option<&str>,
+	) -> Result<Discord> {
+		DiscordBuilder::new().login_cached(path, email, password)
+	}
+
 	/// Log in as a bot account using the given authentication token.
 	///
 	/// The token will automatically be prefixed with "Bot ".
 	pub fn from_bot_token(token: &str) -> Result<Discord> {
-		Ok(Discord::from_token_raw(format!("Bot {}", token.trim())))
+		DiscordBuilder::new().from_bot_token(token)
 	}
 
 	/// Log in as a user account using the given authentication token.
 	pub fn from_user_token(token: &str) -> Result<Discord> {
-		Ok(Discord::from_token_raw(token.trim().to_owned()))
+		DiscordBuilder::new().from_user_token(token)
+	}
+
+	/// Get a client which attaches the given reason to its requests, to be
+	/// shown in the server's audit log.
+	///
+	/// The returned client shares this one's connection pool and rate limits,
+	/// so it is cheap to create one for each action. The reason is recorded
+	/// for any endpoint which appears in the audit log, such as kicks, bans
+	/// and channel, role, member or message changes.
+	///
+	/// ```ignore
+	/// discord.with_reason("Spamming invite links").kick_member(server, user)?;
+	/// ```
+	pub fn with_reason(&self, reason: &str) -> Discord {
+		Discord {
+			rate_limits: self.rate_limits.clone(),
+			client: self.client.clone(),
+			token: self.token.clone(),
+			config: self.config.clone(),
+			reason: Some(reason.to_owned()),
+		}
+	}
+
+	/// Get a client which retries failed requests according to the given
+	/// policy, for example `RetryPolicy::never()` for calls which must not be
+	/// repeated.
+	///
+	/// The returned client shares this one's connection pool and rate limits.
+	pub fn with_retry_policy(&self, retry_policy: RetryPolicy) -> Discord {
+		let mut config = self.config.clone();
+		config.retry_policy = retry_policy;
+		Discord {
+			rate_limits: self.rate_limits.clone(),
+			client: self.client.clone(),
+			token: self.token.clone(),
+			config: config,
+			reason: self.reason.clone(),
+		}
 	}
 
 	/// Log out from the Discord API, invalidating this clients's token.
@@ -291,28 +579,29 @@ impl Discord {
 
 	fn request<'a, F: Fn() -> hyper::client::RequestBuilder<'a>>(
 		&self,
+		method: &str,
 		url: &str,
 		f: F,
 	) -> Result<hyper::client::Response> {
-		self.rate_limits.pre_check(url);
-		let f2 = || {
-			f().header(hyper::header::ContentType::json())
-				.header(hyper::header::Authorization(self.token.clone()))
-		};
-		let result = retry(&f2);
-		if let Ok(response) = result.as_ref() {
-			if self.rate_limits.post_update(url, response) {
-				// we were rate limited, we have slept, it is time to retry
-				// the request once. if it fails the second time, give up
-				debug!("Retrying after having been ratelimited");
-				let result = retry(f2);
-				if let Ok(response) = result.as_ref() {
-					self.rate_limits.post_update(url, response);
-				}
-				return check_status(result);
+		self.request_as(method, url, hyper::header::ContentType::json(), f)
+	}
+
+	fn request_as<'a, F: Fn() -> hyper::client::RequestBuilder<'a>>(
+		&self,
+		method: &str,
+		url: &str,
+		content_type: hyper::header::ContentType,
+		f: F,
+	) -> Result<hyper::client::Response> {
+		rate_limited(&self.rate_limits, &self.config.retry_policy, method, url, || {
+			let builder = f()
+				.header(content_type.clone())
+				.header(hyper::header::Authorization(self.token.clone()));
+			match self.reason {
+				Some(ref reason) => builder.header(AuditLogReason(percent_encode(reason))),
+				None => builder,
 			}
-		}
-		check_status(result)
+		})
 	}
 
 	/// Create a channel.
@@ -357,36 +646,7 @@ impl Discord {
 		f: F,
 	) -> Result<PublicChannel> {
 		// Work around the fact that this supposed PATCH call actually requires all fields
-		let mut map = Object::new();
-		match self.get_channel(channel)? {
-			Channel::Private(_) => return Err(Error::Other("Can not edit private channels")),
-			Channel::Public(channel) => {
-				map.insert("name".into(), channel.name.into());
-				map.insert("position".into(), channel.position.into());
-
-				match channel.kind {
-					ChannelType::Text => {
-						map.insert("topic".into(), json!(channel.topic));
-					}
-					ChannelType::Voice => {
-						map.insert("bitrate".into(), json!(channel.bitrate));
-						map.insert("user_limit".into(), json!(channel.user_limit));
-					}
-					_ => {
-						return Err(Error::Other(stringify!(format!(
-							"Unreachable channel type: {:?}",
-							channel.kind
-						))))
-					}
-				}
-			}
-			Channel::Group(group) => {
-				map.insert("name".into(), json!(group.name));
-			}
-			Channel::Category(_) => {}
-			Channel::News => {}
-			Channel::Store => {}
-		};
+		let map = editable_fields(self.get_channel(channel)?)?;
 		let map = EditChannel::__apply(f, map);
 		let body = serde_json::to_string(&map)?;
 		let response = request!(self, patch(body), "/channels/{}", channel);
@@ -399,6 +659,152 @@ impl Discord {
 		Channel::decode(serde_json::from_reader(response)?)
 	}
 
+	/// Start a thread from an existing message. See `CreateThread` for the
+	/// editable fields.
+	///
+	/// The thread's ID is the same as the message's.
+	pub fn start_thread_from_message<F: FnOnce(CreateThread) -> CreateThread>(
+		&self,
+		channel: ChannelId,
+		message: MessageId,
+		f: F,
+	) -> Result<PublicChannel> {
+		let map = CreateThread::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(
+			self,
+			post(body),
+			"/channels/{}/messages/{}/threads",
+			channel,
+			message
+		);
+		PublicChannel::decode(serde_json::from_reader(response)?)
+	}
+
+	/// Start a thread which is not attached to a message. See `CreateThread`
+	/// for the editable fields.
+	///
+	/// Creates a private thread unless another type is set.
+	pub fn start_thread<F: FnOnce(CreateThread) -> CreateThread>(
+		&self,
+		channel: ChannelId,
+		f: F,
+	) -> Result<PublicChannel> {
+		let map = CreateThread::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, post(body), "/channels/{}/threads", channel);
+		PublicChannel::decode(serde_json::from_reader(response)?)
+	}
+
+	/// Add the current user to a thread. The thread must not be archived.
+	pub fn join_thread(&self, thread: ChannelId) -> Result<()> {
+		check_empty(request!(self, put, "/channels/{}/thread-members/@me", thread))
+	}
+
+	/// Remove the current user from a thread.
+	pub fn leave_thread(&self, thread: ChannelId) -> Result<()> {
+		check_empty(request!(self, delete, "/channels/{}/thread-members/@me", thread))
+	}
+
+	/// Add another user to a thread. The thread must not be archived, and the
+	/// current user must be able to send messages in it.
+	pub fn add_thread_member(&self, thread: ChannelId, user: UserId) -> Result<()> {
+		check_empty(request!(
+			self,
+			put,
+			"/channels/{}/thread-members/{}",
+			thread,
+			user
+		))
+	}
+
+	/// Remove another user from a thread.
+	///
+	/// Requires the `MANAGE_THREADS` permission, or to be the creator of a
+	/// private thread.
+	pub fn remove_thread_member(&self, thread: ChannelId, user: UserId) -> Result<()> {
+		check_empty(request!(
+			self,
+			delete,
+			"/channels/{}/thread-members/{}",
+			thread,
+			user
+		))
+	}
+
+	/// Get the members of a thread.
+	pub fn get_thread_members(&self, thread: ChannelId) -> Result<Vec<ThreadMember>> {
+		let response = request!(self, get, "/channels/{}/thread-members", thread);
+		from_reader(response)
+	}
+
+	/// Get the active threads in a server which the current user can see.
+	pub fn get_active_threads(&self, server: ServerId) -> Result<ThreadList> {
+		let response = request!(self, get, "/guilds/{}/threads/active", server);
+		ThreadList::decode(serde_json::from_reader(response)?)
+	}
+
+	/// Get the archived public threads of a channel, most recently archived
+	/// first, optionally only those archived before the given time.
+	pub fn get_public_archived_threads(
+		&self,
+		channel: ChannelId,
+		before: Option<DateTime<FixedOffset>>,
+		limit: Option<u64>,
+	) -> Result<ThreadList> {
+		self.get_archived_threads(
+			format!("/channels/{}/threads/archived/public", channel),
+			before.map(format_timestamp),
+			limit,
+		)
+	}
+
+	/// Get the archived private threads of a channel, most recently archived
+	/// first, optionally only those archived before the given time.
+	///
+	/// Requires the `MANAGE_THREADS` permission.
+	pub fn get_private_archived_threads(
+		&self,
+		channel: ChannelId,
+		before: Option<DateTime<FixedOffset>>,
+		limit: Option<u64>,
+	) -> Result<ThreadList> {
+		self.get_archived_threads(
+			format!("/channels/{}/threads/archived/private", channel),
+			before.map(format_timestamp),
+			limit,
+		)
+	}
+
+	/// Get the archived private threads of a channel which the current user
+	/// has joined, optionally only those with IDs before the given thread.
+	pub fn get_joined_private_archived_threads(
+		&self,
+		channel: ChannelId,
+		before: Option<ChannelId>,
+		limit: Option<u64>,
+	) -> Result<ThreadList> {
+		self.get_archived_threads(
+			format!("/channels/{}/users/@me/threads/archived/private", channel),
+			before.map(|id| id.to_string()),
+			limit,
+		)
+	}
+
+	fn get_archived_threads(
+		&self,
+		route: String,
+		before: Option<String>,
+		limit: Option<u64>,
+	) -> Result<ThreadList> {
+		let url = with_query(
+			format!("{}{}", self.config.api_base, route),
+			&[("before", before), ("limit", limit.map(|limit| limit.to_string()))],
+		);
+		let response = self.request("get", &url, || self.client.get(&url))?;
+		ThreadList::decode(serde_json::from_reader(response)?)
+	}
+
 	/// Indicate typing on a channel for the next 5 seconds.
 	pub fn broadcast_typing(&self, channel: ChannelId) -> Result<()> {
 		check_empty(request!(self, post, "/channels/{}/typing", channel))
@@ -424,7 +830,8 @@ impl Discord {
 	) -> Result<Vec<Message>> {
 		use std::fmt::Write;
 		let mut url = format!(
-			api_concat!("/channels/{}/messages?limit={}"),
+			"{}/channels/{}/messages?limit={}",
+			self.config.api_base,
 			channel,
 			limit.unwrap_or(50)
 		);
@@ -440,10 +847,17 @@ impl Discord {
 				let _ = write!(url, "&around={}", id);
 			}
 		}
-		let response = self.request(&url, || self.client.get(&url))?;
+		let response = self.request("get", &url, || self.client.get(&url))?;
 		from_reader(response)
 	}
 
+	/// Iterate over a channel's messages, most recent first, fetching pages
+	/// of 100 as needed. See `MessageHistory` for walking forwards or starting
+	/// from a given message.
+	pub fn message_history(&self, channel: ChannelId) -> MessageHistory<'_> {
+		MessageHistory::__new(self, channel)
+	}
+
 	/// Gets the pinned messages for a given channel.
 	pub fn get_pinned_messages(&self, channel: ChannelId) -> Result<Vec<Message>> {
 		let response = request!(self, get, "/channels/{}/pins", channel);
@@ -480,15 +894,14 @@ impl Discord {
 	///
 	/// The `nonce` will be returned in the result and also transmitted to other
 	/// clients. The empty string is a good default if you don't care.
+	///
+	/// To attach files, use `send_message_with_files`.
 	pub fn send_message_ex<F: FnOnce(SendMessage) -> SendMessage>(
 		&self,
 		channel: ChannelId,
 		f: F,
 	) -> Result<Message> {
-		let map = SendMessage::__build(f);
-		let body = serde_json::to_string(&map)?;
-		let response = request!(self, post(body), "/channels/{}/messages", channel);
-		from_reader(response)
+		self.send_message_with_files(channel, Vec::new(), f)
 	}
 
 	/// Edit a previously posted message.
@@ -496,7 +909,8 @@ impl Discord {
 	/// Requires that either the message was posted by this user, or this user
 	/// has permission to manage other members' messages.
 	///
-	/// Not all fields can be edited; see the [docs] for more.
+	/// Not all fields can be edited; see the [docs] for more. To upload new
+	/// files, use `edit_message_with_files`.
 	/// [docs]: https://discord.com/developers/docs/resources/channel#edit-message
 	pub fn edit_message_ex<F: FnOnce(SendMessage) -> SendMessage>(
 		&self,
@@ -504,16 +918,7 @@ impl Discord {
 		message: MessageId,
 		f: F,
 	) -> Result<Message> {
-		let map = SendMessage::__build(f);
-		let body = serde_json::to_string(&map)?;
-		let response = request!(
-			self,
-			patch(body),
-			"/channels/{}/messages/{}",
-			channel,
-			message
-		);
-		from_reader(response)
+		self.edit_message_with_files(channel, message, Vec::new(), f)
 	}
 
 	/// Send a message to a given channel.
@@ -530,7 +935,49 @@ impl Discord {
 		self.send_message_ex(channel, |b| b.content(text).nonce(nonce).tts(tts))
 	}
 
-	/// Edit a previously posted message.
+	/// Send a message with any number of files attached to a given channel.
+	///
+	/// The message may also contain content, embeds, allowed mentions and a
+	/// reply, and is sent as plain JSON if there are no files. See
+	/// `FileUpload` for setting each file's description or marking it as a
+	/// spoiler.
+	pub fn send_message_with_files<F: FnOnce(SendMessage) -> SendMessage>(
+		&self,
+		channel: ChannelId,
+		files: Vec<FileUpload>,
+		f: F,
+	) -> Result<Message> {
+		let map = SendMessage::__build(f);
+		let (content_type, body) = message_body(&map, files)?;
+		let url = format!("{}/channels/{}/messages", self.config.api_base, channel);
+		let response = self.request_as("post", &url, content_type, || self.client.post(&url).body(&body[..]))?;
+		from_reader(response)
+	}
+
+	/// Edit a previously posted message, uploading new files to it.
+	///
+	/// Existing attachments are removed unless kept using
+	/// `SendMessage::keep_attachment`. To remove attachments without
+	/// uploading any, use `edit_message_ex` with `clear_attachments` and
+	/// `keep_attachment`.
+	pub fn edit_message_with_files<F: FnOnce(SendMessage) -> SendMessage>(
+		&self,
+		channel: ChannelId,
+		message: MessageId,
+		files: Vec<FileUpload>,
+		f: F,
+	) -> Result<Message> {
+		let map = SendMessage::__build(f);
+		let (content_type, body) = message_body(&map, files)?;
+		let url = format!(
+			"{}/channels/{}/messages/{}",
+			self.config.api_base, channel, message
+		);
+		let response = self.request_as("patch", &url, content_type, || self.client.patch(&url).body(&body[..]))?;
+		from_reader(response)
+	}
+
+	/// Edit a previously posted message.
 	///
 	/// Requires that either the message was posted by this user, or this user
 	/// has permission to manage other members' messages.
@@ -625,47 +1072,16 @@ impl Discord {
 		&self,
 		channel: ChannelId,
 		text: &str,
-		mut file: R,
+		file: R,
 		filename: &str,
 	) -> Result<Message> {
-		use std::io::Write;
-
-		let url = match hyper::Url::parse(&format!(api_concat!("/channels/{}/messages"), channel)) {
-			Ok(url) => url,
-			Err(_) => return Err(Error::Other("Invalid URL in send_file")),
-		};
-		// NB: We're NOT using the Hyper itegration of multipart in order not to wrestle with the openssl-sys dependency hell.
-		let cr = multipart::mock::ClientRequest::default();
-		let mut multi = multipart::client::Multipart::from_request(cr)?;
-		multi.write_text("content", text)?;
-		multi.write_stream("file", &mut file, Some(filename), None)?;
-		let http_buffer: multipart::mock::HttpBuffer = multi.send()?;
-		fn multipart_mime(bound: &str) -> hyper::mime::Mime {
-			use hyper::mime::{Attr, Mime, SubLevel, TopLevel, Value};
-			Mime(
-				TopLevel::Multipart,
-				SubLevel::Ext("form-data".into()),
-				vec![(Attr::Ext("boundary".into()), Value::Ext(bound.into()))],
-			)
-		}
-
-		let tls = hyper_native_tls::NativeTlsClient::new().expect("Error initializing NativeTlsClient");
-		let connector = hyper::net::HttpsConnector::new(tls);
-		let mut request = hyper::client::Request::with_connector(hyper::method::Method::Post, url, &connector)?;
-		request
-			.headers_mut()
-			.set(hyper::header::Authorization(self.token.clone()));
-		request
-			.headers_mut()
-			.set(hyper::header::UserAgent(USER_AGENT.to_owned()));
-		request
-			.headers_mut()
-			.set(hyper::header::ContentType(multipart_mime(
-				&http_buffer.boundary,
-			)));
-		let mut request = request.start()?;
-		request.write(&http_buffer.buf[..])?;
-		Message::decode(serde_json::from_reader(check_status(request.send())?)?)
+		self.send_message_with_files(channel, vec![FileUpload::new(filename, file)], |b| {
+			if text.is_empty() {
+				b
+			} else {
+				b.content(text)
+			}
+		})
 	}
 
 	/// Acknowledge this message as "read" by this client.
@@ -920,26 +1336,90 @@ impl Discord {
 		from_reader(response)
 	}
 
+	/// Iterate over every user who reacted with the `Emoji` to a `Message`,
+	/// fetching pages of 100 as needed.
+	pub fn all_reactions(
+		&self,
+		channel: ChannelId,
+		message: MessageId,
+		emoji: ReactionEmoji,
+	) -> Paginated<'_, User> {
+		Paginated::__new(pagination::REACTION_PAGE as usize, |user: &User| user.id.0, move |after| {
+			self.get_reactions(
+				channel,
+				message,
+				emoji.clone(),
+				Some(pagination::REACTION_PAGE as i32),
+				after.map(UserId),
+			)
+		})
+	}
+
 	/// Get the list of servers this user knows about.
+	///
+	/// Only the first 200 servers are returned; see `get_servers_paged` or
+	/// `all_servers` for users in more servers than that.
 	pub fn get_servers(&self) -> Result<Vec<ServerInfo>> {
 		let response = request!(self, get, "/users/@me/guilds");
 		from_reader(response)
 	}
 
+	/// Get a page of the servers this user knows about, ordered by ID.
+	///
+	/// The default `limit` is 200, which is also the maximum. The optional
+	/// value of `after` is the ID of the server to retrieve the next page after.
+	pub fn get_servers_paged(&self, limit: Option<u32>, after: Option<ServerId>) -> Result<Vec<ServerInfo>> {
+		let mut endpoint = format!("/users/@me/guilds?limit={}", limit.unwrap_or(pagination::SERVER_PAGE));
+		if let Some(after) = after {
+			use std::fmt::Write;
+			let _ = write!(endpoint, "&after={}", after);
+		}
+		let response = request!(self, get, "{}", endpoint);
+		from_reader(response)
+	}
+
+	/// Iterate over every server this user knows about, fetching pages of 200
+	/// as needed.
+	pub fn all_servers(&self) -> Paginated<'_, ServerInfo> {
+		Paginated::__new(pagination::SERVER_PAGE as usize, |server: &ServerInfo| server.id.0, move |after| {
+			self.get_servers_paged(Some(pagination::SERVER_PAGE), after.map(ServerId))
+		})
+	}
+
 	/// Gets a specific server.
 	pub fn get_server(&self, server_id: ServerId) -> Result<Server> {
 		let response = request!(self, get, "/guilds/{}", server_id);
 		from_reader(response)
 	}
 
-	/// Gets the list of a specific server's members.
-	pub fn get_server_members(&self, server_id: ServerId, limit: Option<u32>, after: Option<u32>) -> Result<Vec<Member>> {
-		let limit = limit.unwrap_or(1);
-		let after = after.unwrap_or(0);
-		let response = request!(self, get, "/guilds/{}/members?limit={}&after={}", server_id, limit, after);
+	/// Gets a page of a specific server's members, ordered by user ID.
+	///
+	/// The default `limit` is 1, and the maximum is 1000. The optional value
+	/// of `after` is the ID of the user to retrieve the next members after.
+	/// Requires the server members intent.
+	pub fn get_server_members(
+		&self,
+		server_id: ServerId,
+		limit: Option<u32>,
+		after: Option<UserId>,
+	) -> Result<Vec<Member>> {
+		let mut endpoint = format!("/guilds/{}/members?limit={}", server_id, limit.unwrap_or(1));
+		if let Some(after) = after {
+			use std::fmt::Write;
+			let _ = write!(endpoint, "&after={}", after);
+		}
+		let response = request!(self, get, "{}", endpoint);
 		from_reader(response)
 	}
 
+	/// Iterate over every member of a server, fetching pages of 1000 as
+	/// needed. Requires the server members intent.
+	pub fn all_server_members(&self, server_id: ServerId) -> Paginated<'_, Member> {
+		Paginated::__new(pagination::MEMBER_PAGE as usize, |member: &Member| member.user.id.0, move |after| {
+			self.get_server_members(server_id, Some(pagination::MEMBER_PAGE), after.map(UserId))
+		})
+	}
+
 	/// Create a new server with the given name.
 	pub fn create_server(&self, name: &str, region: &str, icon: Option<&str>) -> Result<Server> {
 		let map = json! {{
@@ -952,6 +1432,21 @@ impl Discord {
 		from_reader(response)
 	}
 
+	/// Create a new server from a template, with the given name and
+	/// optional icon.
+	///
+	/// The template should either be a URL of the form
+	/// `https://discord.new/CODE`, or a string containing just the `CODE`.
+	pub fn create_server_from_template(&self, template: &str, name: &str, icon: Option<&str>) -> Result<Server> {
+		let map = json! {{
+			"name": name,
+			"icon": icon,
+		}};
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, post(body), "/guilds/templates/{}", resolve_template(template));
+		from_reader(response)
+	}
+
 	/// Edit a server's information. See `EditServer` for the editable fields.
 	///
 	/// ```ignore
@@ -1036,6 +1531,28 @@ impl Discord {
 		from_reader(response)
 	}
 
+	/// Get a page of the ban list for the given server, ordered by user ID.
+	///
+	/// The default `limit` is 1000, which is also the maximum. The optional
+	/// value of `after` is the ID of the user to retrieve the next bans after.
+	pub fn get_bans_paged(&self, server: ServerId, limit: Option<u32>, after: Option<UserId>) -> Result<Vec<Ban>> {
+		let mut endpoint = format!("/guilds/{}/bans?limit={}", server, limit.unwrap_or(pagination::BAN_PAGE));
+		if let Some(after) = after {
+			use std::fmt::Write;
+			let _ = write!(endpoint, "&after={}", after);
+		}
+		let response = request!(self, get, "{}", endpoint);
+		from_reader(response)
+	}
+
+	/// Iterate over the whole ban list for the given server, fetching pages of
+	/// 1000 as needed.
+	pub fn all_bans(&self, server: ServerId) -> Paginated<'_, Ban> {
+		Paginated::__new(pagination::BAN_PAGE as usize, |ban: &Ban| ban.user.id.0, move |after| {
+			self.get_bans_paged(server, Some(pagination::BAN_PAGE), after.map(UserId))
+		})
+	}
+
 	/// Ban a user from the server, optionally deleting their recent messages.
 	///
 	/// Zero may be passed for `delete_message_days` if no deletion is desired.
@@ -1113,6 +1630,68 @@ impl Discord {
 		Invite::decode(serde_json::from_reader(response)?)
 	}
 
+	/// Create a webhook for a channel.
+	///
+	/// `read_image` may be used to build an `avatar` string. Requires the
+	/// `MANAGE_WEBHOOKS` permission.
+	pub fn create_webhook(
+		&self,
+		channel: ChannelId,
+		name: &str,
+		avatar: Option<&str>,
+	) -> Result<Webhook> {
+		let map = json! {{
+			"name": name,
+			"avatar": avatar,
+		}};
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, post(body), "/channels/{}/webhooks", channel);
+		from_reader(response)
+	}
+
+	/// Get the webhooks of a channel.
+	pub fn get_channel_webhooks(&self, channel: ChannelId) -> Result<Vec<Webhook>> {
+		let response = request!(self, get, "/channels/{}/webhooks", channel);
+		from_reader(response)
+	}
+
+	/// Get the webhooks of every channel in a server.
+	pub fn get_server_webhooks(&self, server: ServerId) -> Result<Vec<Webhook>> {
+		let response = request!(self, get, "/guilds/{}/webhooks", server);
+		from_reader(response)
+	}
+
+	/// Get information about a webhook.
+	pub fn get_webhook(&self, webhook: WebhookId) -> Result<Webhook> {
+		let response = request!(self, get, "/webhooks/{}", webhook);
+		from_reader(response)
+	}
+
+	/// Edit a webhook's details. See `EditWebhook` for the editable fields.
+	///
+	/// ```ignore
+	/// // Rename a webhook and move it to another channel
+	/// discord.edit_webhook(webhook_id, |w| w
+	///     .name("Alerts")
+	///     .channel(channel_id)
+	/// );
+	/// ```
+	pub fn edit_webhook<F: FnOnce(EditWebhook) -> EditWebhook>(
+		&self,
+		webhook: WebhookId,
+		f: F,
+	) -> Result<Webhook> {
+		let map = EditWebhook::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, patch(body), "/webhooks/{}", webhook);
+		from_reader(response)
+	}
+
+	/// Delete a webhook.
+	pub fn delete_webhook(&self, webhook: WebhookId) -> Result<()> {
+		check_empty(request!(self, delete, "/webhooks/{}", webhook))
+	}
+
 	/// Retrieve a member object for a server given the member's user id.
 	pub fn get_member(&self, server: ServerId, user: UserId) -> Result<Member> {
 		let response = request!(self, get, "/guilds/{}/members/{}", server, user);
@@ -1283,13 +1862,30 @@ impl Discord {
 
 	/// Get the URL at which a user's avatar is located.
 	pub fn get_user_avatar_url(&self, user: UserId, avatar: &str) -> String {
-		format!(api_concat!("/users/{}/avatars/{}.jpg"), user, avatar)
+		self.cdn_url(cdn::user_avatar(user, avatar))
+	}
+
+	/// Format an image URL from the `cdn` module using the configured CDN
+	/// base.
+	pub fn cdn_url(&self, image: cdn::ImageUrl) -> String {
+		image.base(&self.config.cdn_base).into()
 	}
 
 	/// Download a user's avatar.
 	pub fn get_user_avatar(&self, user: UserId, avatar: &str) -> Result<Vec<u8>> {
+		self.download_cdn(&self.get_user_avatar_url(user, avatar))
+	}
+
+	/// Download a file, such as an image URL built with the `cdn` module.
+	///
+	/// The request is sent without authorization, and retried according to
+	/// the client's `RetryPolicy`. The CDN sends no rate limit headers, so
+	/// it is never held back by the rate limits of the API.
+	pub fn download_cdn(&self, url: &str) -> Result<Vec<u8>> {
 		use std::io::Read;
-		let mut response = retry(|| self.client.get(&self.get_user_avatar_url(user, avatar)))?;
+		let mut response = rate_limited(&self.rate_limits, &self.config.retry_policy, "get", url, || {
+			self.client.get(url)
+		})?;
 		let mut vec = Vec::new();
 		response.read_to_end(&mut vec)?;
 		Ok(vec)
@@ -1420,6 +2016,353 @@ impl Discord {
 		from_reader(response)
 	}
 
+	/// Get entries from a server's audit log, most recent first, along with
+	/// the users, webhooks, integrations and threads they refer to.
+	///
+	/// Requires the "VIEW_AUDIT_LOG" permission. See `GetAuditLog` for the
+	/// available filters; use `|f| f` for the 50 most recent entries.
+	pub fn get_audit_log<F: FnOnce(GetAuditLog) -> GetAuditLog>(
+		&self,
+		server: ServerId,
+		f: F,
+	) -> Result<AuditLog> {
+		let map = GetAuditLog::__build(f);
+		let params: Vec<_> = map
+			.iter()
+			.map(|(key, value)| {
+				let value = match *value {
+					serde_json::Value::String(ref value) => value.clone(),
+					ref value => value.to_string(),
+				};
+				(&key[..], Some(value))
+			})
+			.collect();
+		let url = with_query(format!("{}/guilds/{}/audit-logs", self.config.api_base, server), &params);
+		let response = self.request("get", &url, || self.client.get(&url))?;
+		AuditLog::decode(serde_json::from_reader(response)?)
+	}
+
+	/// Get the scheduled events of a server which have not yet ended,
+	/// optionally with their number of subscribed users.
+	pub fn get_scheduled_events(&self, server: ServerId, with_user_count: bool) -> Result<Vec<ScheduledEvent>> {
+		let response = request!(
+			self,
+			get,
+			"/guilds/{}/scheduled-events?with_user_count={}",
+			server,
+			with_user_count
+		);
+		from_reader(response)
+	}
+
+	/// Get a scheduled event, optionally with its number of subscribed users.
+	pub fn get_scheduled_event(
+		&self,
+		server: ServerId,
+		event: ScheduledEventId,
+		with_user_count: bool,
+	) -> Result<ScheduledEvent> {
+		let response = request!(
+			self,
+			get,
+			"/guilds/{}/scheduled-events/{}?with_user_count={}",
+			server,
+			event,
+			with_user_count
+		);
+		from_reader(response)
+	}
+
+	/// Schedule an event on a server. See `EditScheduledEvent` for the
+	/// fields; a name, entity type and start time are required.
+	///
+	/// Requires the "MANAGE_EVENTS" permission.
+	pub fn create_scheduled_event<F: FnOnce(EditScheduledEvent) -> EditScheduledEvent>(
+		&self,
+		server: ServerId,
+		f: F,
+	) -> Result<ScheduledEvent> {
+		let mut map = EditScheduledEvent::__build(f);
+		map.entry("privacy_level")
+			.or_insert_with(|| json!(ScheduledEventPrivacyLevel::ServerOnly));
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, post(body), "/guilds/{}/scheduled-events", server);
+		from_reader(response)
+	}
+
+	/// Modify a scheduled event, including starting, ending or canceling it
+	/// by setting its status.
+	pub fn edit_scheduled_event<F: FnOnce(EditScheduledEvent) -> EditScheduledEvent>(
+		&self,
+		server: ServerId,
+		event: ScheduledEventId,
+		f: F,
+	) -> Result<ScheduledEvent> {
+		let map = EditScheduledEvent::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, patch(body), "/guilds/{}/scheduled-events/{}", server, event);
+		from_reader(response)
+	}
+
+	/// Delete a scheduled event.
+	pub fn delete_scheduled_event(&self, server: ServerId, event: ScheduledEventId) -> Result<()> {
+		check_empty(request!(self, delete, "/guilds/{}/scheduled-events/{}", server, event))
+	}
+
+	/// Get up to 100 users subscribed to a scheduled event, ordered by ID,
+	/// optionally only those after the given user and with their server
+	/// memberships.
+	pub fn get_scheduled_event_users(
+		&self,
+		server: ServerId,
+		event: ScheduledEventId,
+		limit: Option<u32>,
+		after: Option<UserId>,
+		with_member: bool,
+	) -> Result<Vec<ScheduledEventUser>> {
+		let mut endpoint = format!(
+			"/guilds/{}/scheduled-events/{}/users?limit={}&with_member={}",
+			server,
+			event,
+			limit.unwrap_or(pagination::EVENT_USER_PAGE),
+			with_member
+		);
+		if let Some(after) = after {
+			use std::fmt::Write;
+			let _ = write!(endpoint, "&after={}", after);
+		}
+		let response = request!(self, get, "{}", endpoint);
+		from_reader(response)
+	}
+
+	/// Iterate over all users subscribed to a scheduled event, fetching pages
+	/// of 100 as needed.
+	pub fn all_scheduled_event_users(
+		&self,
+		server: ServerId,
+		event: ScheduledEventId,
+		with_member: bool,
+	) -> Paginated<'_, ScheduledEventUser> {
+		Paginated::__new(
+			pagination::EVENT_USER_PAGE as usize,
+			|user: &ScheduledEventUser| user.user.id.0,
+			move |after| {
+				self.get_scheduled_event_users(
+					server,
+					event,
+					Some(pagination::EVENT_USER_PAGE),
+					after.map(UserId),
+					with_member,
+				)
+			},
+		)
+	}
+
+	/// Get a standard or server sticker.
+	pub fn get_sticker(&self, sticker: StickerId) -> Result<Sticker> {
+		let response = request!(self, get, "/stickers/{}", sticker);
+		from_reader(response)
+	}
+
+	/// Get the stickers uploaded to a server.
+	pub fn get_server_stickers(&self, server: ServerId) -> Result<Vec<Sticker>> {
+		let response = request!(self, get, "/guilds/{}/stickers", server);
+		from_reader(response)
+	}
+
+	/// Get a sticker uploaded to a server.
+	pub fn get_server_sticker(&self, server: ServerId, sticker: StickerId) -> Result<Sticker> {
+		let response = request!(self, get, "/guilds/{}/stickers/{}", server, sticker);
+		from_reader(response)
+	}
+
+	/// Upload a sticker to a server. See `EditSticker` for the fields; a
+	/// name and tags are required.
+	///
+	/// The file must be a PNG, APNG, GIF or Lottie JSON file of at most 512
+	/// KiB. Requires the "MANAGE_EMOJIS" permission.
+	pub fn create_server_sticker<F: FnOnce(EditSticker) -> EditSticker>(
+		&self,
+		server: ServerId,
+		file: FileUpload,
+		f: F,
+	) -> Result<Sticker> {
+		let map = EditSticker::__build(f);
+		let fields = ["name", "description", "tags"]
+			.iter()
+			.map(|&key| {
+				let text = map.get(key).and_then(|v| v.as_str()).unwrap_or("").to_owned();
+				(key, text)
+			})
+			.collect();
+		let (content_type, body) = multipart_form(fields, vec![("file".to_owned(), file)])?;
+		let url = format!("{}/guilds/{}/stickers", self.config.api_base, server);
+		let response = self.request_as("post", &url, content_type, || self.client.post(&url).body(&body[..]))?;
+		from_reader(response)
+	}
+
+	/// Modify a sticker uploaded to a server.
+	pub fn edit_server_sticker<F: FnOnce(EditSticker) -> EditSticker>(
+		&self,
+		server: ServerId,
+		sticker: StickerId,
+		f: F,
+	) -> Result<Sticker> {
+		let map = EditSticker::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, patch(body), "/guilds/{}/stickers/{}", server, sticker);
+		from_reader(response)
+	}
+
+	/// Delete a sticker uploaded to a server.
+	pub fn delete_server_sticker(&self, server: ServerId, sticker: StickerId) -> Result<()> {
+		check_empty(request!(self, delete, "/guilds/{}/stickers/{}", server, sticker))
+	}
+
+	/// Get the integrations of a server.
+	///
+	/// Requires the "MANAGE_SERVER" permission.
+	pub fn get_server_integrations(&self, server: ServerId) -> Result<Vec<Integration>> {
+		let response = request!(self, get, "/guilds/{}/integrations", server);
+		from_reader(response)
+	}
+
+	/// Remove an integration from a server, along with any webhooks and bot
+	/// user it added.
+	pub fn delete_server_integration(&self, server: ServerId, integration: IntegrationId) -> Result<()> {
+		check_empty(request!(self, delete, "/guilds/{}/integrations/{}", server, integration))
+	}
+
+	/// Get information about a server template.
+	///
+	/// The template should either be a URL of the form
+	/// `https://discord.new/CODE`, or a string containing just the `CODE`.
+	pub fn get_template(&self, template: &str) -> Result<ServerTemplate> {
+		let response = request!(self, get, "/guilds/templates/{}", resolve_template(template));
+		from_reader(response)
+	}
+
+	/// Get the templates created from a server.
+	///
+	/// Requires the "MANAGE_SERVER" permission.
+	pub fn get_server_templates(&self, server: ServerId) -> Result<Vec<ServerTemplate>> {
+		let response = request!(self, get, "/guilds/{}/templates", server);
+		from_reader(response)
+	}
+
+	/// Create a template from a server's current settings, roles and
+	/// channels. See `EditServerTemplate` for the fields; a name is required.
+	pub fn create_server_template<F: FnOnce(EditServerTemplate) -> EditServerTemplate>(
+		&self,
+		server: ServerId,
+		f: F,
+	) -> Result<ServerTemplate> {
+		let map = EditServerTemplate::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, post(body), "/guilds/{}/templates", server);
+		from_reader(response)
+	}
+
+	/// Update a template to match its server's current settings, roles and
+	/// channels.
+	pub fn sync_server_template(&self, server: ServerId, code: &str) -> Result<ServerTemplate> {
+		let response = request!(self, put, "/guilds/{}/templates/{}", server, code);
+		from_reader(response)
+	}
+
+	/// Modify a template's name or description.
+	pub fn edit_server_template<F: FnOnce(EditServerTemplate) -> EditServerTemplate>(
+		&self,
+		server: ServerId,
+		code: &str,
+		f: F,
+	) -> Result<ServerTemplate> {
+		let map = EditServerTemplate::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, patch(body), "/guilds/{}/templates/{}", server, code);
+		from_reader(response)
+	}
+
+	/// Delete a template, returning it.
+	pub fn delete_server_template(&self, server: ServerId, code: &str) -> Result<ServerTemplate> {
+		let response = request!(self, delete, "/guilds/{}/templates/{}", server, code);
+		from_reader(response)
+	}
+
+	/// Get the live stage in a stage channel.
+	pub fn get_stage_instance(&self, channel: ChannelId) -> Result<StageInstance> {
+		let response = request!(self, get, "/stage-instances/{}", channel);
+		from_reader(response)
+	}
+
+	/// Start a stage in a stage channel. See `EditStageInstance` for the
+	/// fields; a topic is required.
+	///
+	/// Requires the "MANAGE_CHANNELS", "VOICE_MUTE_MEMBERS" and
+	/// "VOICE_MOVE_MEMBERS" permissions.
+	pub fn create_stage_instance<F: FnOnce(EditStageInstance) -> EditStageInstance>(
+		&self,
+		channel: ChannelId,
+		f: F,
+	) -> Result<StageInstance> {
+		let mut map = EditStageInstance::__build(f);
+		map.insert("channel_id".into(), json!(channel));
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, post(body), "/stage-instances");
+		from_reader(response)
+	}
+
+	/// Modify the live stage in a stage channel.
+	pub fn edit_stage_instance<F: FnOnce(EditStageInstance) -> EditStageInstance>(
+		&self,
+		channel: ChannelId,
+		f: F,
+	) -> Result<StageInstance> {
+		let map = EditStageInstance::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, patch(body), "/stage-instances/{}", channel);
+		from_reader(response)
+	}
+
+	/// End the live stage in a stage channel.
+	pub fn delete_stage_instance(&self, channel: ChannelId) -> Result<()> {
+		check_empty(request!(self, delete, "/stage-instances/{}", channel))
+	}
+
+	/// Change the current user's voice state in the given stage channel,
+	/// which they must already be in.
+	///
+	/// Unsuppressing oneself requires the "VOICE_MUTE_MEMBERS" permission, and
+	/// requesting to speak requires the "REQUEST_TO_SPEAK" permission.
+	pub fn edit_own_voice_state<F: FnOnce(EditVoiceState) -> EditVoiceState>(
+		&self,
+		server: ServerId,
+		channel: ChannelId,
+		f: F,
+	) -> Result<()> {
+		let mut map = EditVoiceState::__build(f);
+		map.insert("channel_id".into(), json!(channel));
+		let body = serde_json::to_string(&map)?;
+		check_empty(request!(self, patch(body), "/guilds/{}/voice-states/@me", server))
+	}
+
+	/// Change another user's voice state in the given stage channel, which
+	/// they must already be in.
+	///
+	/// Requires the "VOICE_MUTE_MEMBERS" permission.
+	pub fn edit_voice_state<F: FnOnce(EditVoiceState) -> EditVoiceState>(
+		&self,
+		server: ServerId,
+		user: UserId,
+		channel: ChannelId,
+		f: F,
+	) -> Result<()> {
+		let mut map = EditVoiceState::__build(f);
+		map.insert("channel_id".into(), json!(channel));
+		let body = serde_json::to_string(&map)?;
+		check_empty(request!(self, patch(body), "/guilds/{}/voice-states/{}", server, user))
+	}
+
 	/// Sets a note for the user that is readable only to the currently logged
 	/// in user.
 	///
@@ -1437,6 +2380,286 @@ impl Discord {
 		from_reader(response)
 	}
 
+	/// Get the global application commands of an application.
+	///
+	/// For bots, the application ID is the same as the bot's user ID.
+	pub fn get_global_commands(&self, application: ApplicationId) -> Result<Vec<ApplicationCommand>> {
+		let response = request!(self, get, "/applications/{}/commands", application);
+		from_reader(response)
+	}
+
+	/// Create a global application command, available in every server the
+	/// application is in. Creating a command with the same name as an existing
+	/// command of the same type overwrites it.
+	pub fn create_global_command<F: FnOnce(CreateCommand) -> CreateCommand>(
+		&self,
+		application: ApplicationId,
+		f: F,
+	) -> Result<ApplicationCommand> {
+		let map = CreateCommand::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, post(body), "/applications/{}/commands", application);
+		from_reader(response)
+	}
+
+	/// Edit a global application command. Fields not set are left unchanged.
+	pub fn edit_global_command<F: FnOnce(CreateCommand) -> CreateCommand>(
+		&self,
+		application: ApplicationId,
+		command: CommandId,
+		f: F,
+	) -> Result<ApplicationCommand> {
+		let map = CreateCommand::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(
+			self,
+			patch(body),
+			"/applications/{}/commands/{}",
+			application,
+			command
+		);
+		from_reader(response)
+	}
+
+	/// Delete a global application command.
+	pub fn delete_global_command(&self, application: ApplicationId, command: CommandId) -> Result<()> {
+		check_empty(request!(
+			self,
+			delete,
+			"/applications/{}/commands/{}",
+			application,
+			command
+		))
+	}
+
+	/// Replace all global application commands with the given list.
+	///
+	/// Existing commands not in the list are deleted.
+	pub fn bulk_overwrite_global_commands<F: FnOnce(CreateCommands) -> CreateCommands>(
+		&self,
+		application: ApplicationId,
+		f: F,
+	) -> Result<Vec<ApplicationCommand>> {
+		let list = CreateCommands::__build(f);
+		let body = serde_json::to_string(&list)?;
+		let response = request!(self, put(body), "/applications/{}/commands", application);
+		from_reader(response)
+	}
+
+	/// Get the application commands registered for a single server.
+	pub fn get_server_commands(
+		&self,
+		application: ApplicationId,
+		server: ServerId,
+	) -> Result<Vec<ApplicationCommand>> {
+		let response = request!(
+			self,
+			get,
+			"/applications/{}/guilds/{}/commands",
+			application,
+			server
+		);
+		from_reader(response)
+	}
+
+	/// Create an application command available only in the given server.
+	///
+	/// Unlike global commands, server commands are available immediately.
+	pub fn create_server_command<F: FnOnce(CreateCommand) -> CreateCommand>(
+		&self,
+		application: ApplicationId,
+		server: ServerId,
+		f: F,
+	) -> Result<ApplicationCommand> {
+		let map = CreateCommand::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(
+			self,
+			post(body),
+			"/applications/{}/guilds/{}/commands",
+			application,
+			server
+		);
+		from_reader(response)
+	}
+
+	/// Edit a server application command. Fields not set are left unchanged.
+	pub fn edit_server_command<F: FnOnce(CreateCommand) -> CreateCommand>(
+		&self,
+		application: ApplicationId,
+		server: ServerId,
+		command: CommandId,
+		f: F,
+	) -> Result<ApplicationCommand> {
+		let map = CreateCommand::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(
+			self,
+			patch(body),
+			"/applications/{}/guilds/{}/commands/{}",
+			application,
+			server,
+			command
+		);
+		from_reader(response)
+	}
+
+	/// Delete a server application command.
+	pub fn delete_server_command(
+		&self,
+		application: ApplicationId,
+		server: ServerId,
+		command: CommandId,
+	) -> Result<()> {
+		check_empty(request!(
+			self,
+			delete,
+			"/applications/{}/guilds/{}/commands/{}",
+			application,
+			server,
+			command
+		))
+	}
+
+	/// Replace all application commands of a server with the given list.
+	///
+	/// Existing commands not in the list are deleted.
+	pub fn bulk_overwrite_server_commands<F: FnOnce(CreateCommands) -> CreateCommands>(
+		&self,
+		application: ApplicationId,
+		server: ServerId,
+		f: F,
+	) -> Result<Vec<ApplicationCommand>> {
+		let list = CreateCommands::__build(f);
+		let body = serde_json::to_string(&list)?;
+		let response = request!(
+			self,
+			put(body),
+			"/applications/{}/guilds/{}/commands",
+			application,
+			server
+		);
+		from_reader(response)
+	}
+
+	/// Send the initial response to an interaction. See the
+	/// `CreateInteractionResponse` struct for the kinds of response.
+	///
+	/// Must be called within 3 seconds of receiving the interaction; use a
+	/// deferred response if more time is needed.
+	pub fn create_interaction_response<F: FnOnce(CreateInteractionResponse) -> CreateInteractionResponse>(
+		&self,
+		interaction: InteractionId,
+		token: &str,
+		f: F,
+	) -> Result<()> {
+		let map = CreateInteractionResponse::__build(f);
+		let body = serde_json::to_string(&map)?;
+		check_empty(request!(
+			self,
+			post(body),
+			"/interactions/{}/{}/callback",
+			interaction,
+			token
+		))
+	}
+
+	/// Get the initial response to an interaction.
+	pub fn get_original_response(&self, application: ApplicationId, token: &str) -> Result<Message> {
+		let response = request!(
+			self,
+			get,
+			"/webhooks/{}/{}/messages/@original",
+			application,
+			token
+		);
+		from_reader(response)
+	}
+
+	/// Edit the initial response to an interaction, or send the message of a
+	/// deferred response.
+	pub fn edit_original_response<F: FnOnce(InteractionMessage) -> InteractionMessage>(
+		&self,
+		application: ApplicationId,
+		token: &str,
+		f: F,
+	) -> Result<Message> {
+		let map = InteractionMessage::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(
+			self,
+			patch(body),
+			"/webhooks/{}/{}/messages/@original",
+			application,
+			token
+		);
+		from_reader(response)
+	}
+
+	/// Delete the initial response to an interaction.
+	pub fn delete_original_response(&self, application: ApplicationId, token: &str) -> Result<()> {
+		check_empty(request!(
+			self,
+			delete,
+			"/webhooks/{}/{}/messages/@original",
+			application,
+			token
+		))
+	}
+
+	/// Send an additional message in response to an interaction.
+	///
+	/// Follow-up messages may be sent for 15 minutes after the interaction.
+	pub fn create_followup_message<F: FnOnce(InteractionMessage) -> InteractionMessage>(
+		&self,
+		application: ApplicationId,
+		token: &str,
+		f: F,
+	) -> Result<Message> {
+		let map = InteractionMessage::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, post(body), "/webhooks/{}/{}", application, token);
+		from_reader(response)
+	}
+
+	/// Edit a follow-up message sent in response to an interaction.
+	pub fn edit_followup_message<F: FnOnce(InteractionMessage) -> InteractionMessage>(
+		&self,
+		application: ApplicationId,
+		token: &str,
+		message: MessageId,
+		f: F,
+	) -> Result<Message> {
+		let map = InteractionMessage::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(
+			self,
+			patch(body),
+			"/webhooks/{}/{}/messages/{}",
+			application,
+			token,
+			message
+		);
+		from_reader(response)
+	}
+
+	/// Delete a follow-up message sent in response to an interaction.
+	pub fn delete_followup_message(
+		&self,
+		application: ApplicationId,
+		token: &str,
+		message: MessageId,
+	) -> Result<()> {
+		check_empty(request!(
+			self,
+			delete,
+			"/webhooks/{}/{}/messages/{}",
+			application,
+			token,
+			message
+		))
+	}
+
 	/// Retrieves the number of guild shards Discord suggests to use based on
 	/// the number of guilds.
 	///
@@ -1487,7 +2710,7 @@ impl Discord {
 	/// received.
 	pub fn connection_builder(&self) -> Result<connection::ConnectionBuilder> {
 		let url = self.get_gateway_url()?;
-		Ok(connection::ConnectionBuilder::new(url, &self.token))
+		Ok(connection::ConnectionBuilder::new(url, &self.token, &self.config))
 	}
 
 	fn get_gateway_url(&self) -> Result<String> {
@@ -1504,6 +2727,53 @@ fn from_reader<T: serde::de::DeserializeOwned, R: std::io::Read>(r: R) -> Result
 	serde_json::from_reader(r).map_err(From::from)
 }
 
+/// The current values of every field of a channel which `edit_channel` sends,
+/// since Discord requires them all.
+fn editable_fields(channel: Channel) -> Result<Object> {
+	let mut map = Object::new();
+	match channel {
+		Channel::Private(_) => return Err(Error::Other("Can not edit private channels")),
+		Channel::Public(channel) => {
+			if !channel.is_thread() {
+				map.insert("position".into(), channel.position.into());
+			}
+			map.insert("name".into(), channel.name.into());
+
+			match channel.kind {
+				ChannelType::Text => {
+					map.insert("topic".into(), json!(channel.topic));
+				}
+				ChannelType::Voice | ChannelType::StageVoice => {
+					map.insert("bitrate".into(), json!(channel.bitrate));
+					map.insert("user_limit".into(), json!(channel.user_limit));
+				}
+				ChannelType::NewsThread | ChannelType::PublicThread | ChannelType::PrivateThread => {}
+				_ => {
+					return Err(Error::Other(stringify!(format!(
+						"Unreachable channel type: {:?}",
+						channel.kind
+					))))
+				}
+			}
+		}
+		Channel::Group(group) => {
+			map.insert("name".into(), json!(group.name));
+		}
+		Channel::Category(_) => {}
+		Channel::News => {}
+		Channel::Store => {}
+	};
+	Ok(map)
+}
+
+/// Format a timestamp for use in a query string, which must avoid `+`.
+fn format_timestamp(timestamp: DateTime<FixedOffset>) -> String {
+	timestamp
+		.with_timezone(&Utc)
+		.format("%Y-%m-%dT%H:%M:%S%.fZ")
+		.to_string()
+}
+
 /// Read an image from a file into a string suitable for upload.
 ///
 /// If the file's extension is `.png`, the claimed media type will be `image/png`, or `image/jpg`
@@ -1525,46 +2795,126 @@ pub fn read_image<P: AsRef<::std::path::Path>>(path: P) -> Result<String> {
 }
 
 /// Retrieves the current unresolved incidents from the status page.
+///
+/// Use `DiscordBuilder::get_unresolved_incidents` to configure the transport.
 pub fn get_unresolved_incidents() -> Result<Vec<Incident>> {
-	let client = tls_client();
-	let response = retry(|| client.get(status_concat!("/incidents/unresolved.json")))?;
-	let mut json: Object = serde_json::from_reader(response)?;
-
-	match json.remove("incidents") {
-		Some(incidents) => decode_array(incidents, Incident::decode),
-		None => Ok(vec![]),
-	}
+	DiscordBuilder::new().get_unresolved_incidents()
 }
 
 /// Retrieves the active maintenances from the status page.
+///
+/// Use `DiscordBuilder::get_active_maintenances` to configure the transport.
 pub fn get_active_maintenances() -> Result<Vec<Maintenance>> {
-	let client = tls_client();
-	let response = check_status(retry(|| {
-		client.get(status_concat!("/scheduled-maintenances/active.json"))
-	}))?;
-	let mut json: Object = serde_json::from_reader(response)?;
-
-	match json.remove("scheduled_maintenances") {
-		Some(scheduled_maintenances) => decode_array(scheduled_maintenances, Maintenance::decode),
-		None => Ok(vec![]),
-	}
+	DiscordBuilder::new().get_active_maintenances()
 }
 
 /// Retrieves the upcoming maintenances from the status page.
+///
+/// Use `DiscordBuilder::get_upcoming_maintenances` to configure the transport.
 pub fn get_upcoming_maintenances() -> Result<Vec<Maintenance>> {
-	let client = tls_client();
-	let response = check_status(retry(|| {
-		client.get(status_concat!("/scheduled-maintenances/upcoming.json"))
-	}))?;
-	let mut json: Object = serde_json::from_reader(response)?;
+	DiscordBuilder::new().get_upcoming_maintenances()
+}
+
+/// A file to be uploaded as a message attachment.
+///
+/// ```ignore
+/// let chart = FileUpload::new("chart.png", File::open("chart.png")?)
+///     .description("Weekly active users");
+/// ```
+pub struct FileUpload<'a> {
+	filename: String,
+	description: Option<String>,
+	reader: Box<dyn std::io::Read + 'a>,
+}
+
+impl<'a> FileUpload<'a> {
+	/// Upload the contents of `reader` under the given filename.
+	pub fn new<R: std::io::Read + 'a>(filename: &str, reader: R) -> FileUpload<'a> {
+		FileUpload {
+			filename: filename.to_owned(),
+			description: None,
+			reader: Box::new(reader),
+		}
+	}
+
+	/// Set the attachment's description, used as alt text for images.
+	pub fn description(mut self, description: &str) -> Self {
+		self.description = Some(description.to_owned());
+		self
+	}
+
+	/// Hide the attachment behind a spoiler warning.
+	pub fn spoiler(mut self) -> Self {
+		if !self.filename.starts_with("SPOILER_") {
+			self.filename.insert_str(0, "SPOILER_");
+		}
+		self
+	}
+}
+
+/// Build the body of a message: plain JSON, or a `multipart/form-data` body
+/// carrying a JSON payload if there are files.
+fn message_body(
+	payload: &Object,
+	files: Vec<FileUpload>,
+) -> Result<(hyper::header::ContentType, Vec<u8>)> {
+	if files.is_empty() {
+		return Ok((hyper::header::ContentType::json(), serde_json::to_vec(payload)?));
+	}
 
-	match json.remove("scheduled_maintenances") {
-		Some(scheduled_maintenances) => decode_array(scheduled_maintenances, Maintenance::decode),
-		None => Ok(vec![]),
+	// NB: We're NOT using the Hyper itegration of multipart in order not to wrestle with the openssl-sys dependency hell.
+	// Describe each uploaded file after any existing attachments being kept.
+	let mut payload = payload.clone();
+	{
+		let attachments = payload
+			.entry("attachments")
+			.or_insert_with(|| serde_json::Value::Array(vec![]));
+		if let serde_json::Value::Array(ref mut attachments) = *attachments {
+			for (i, file) in files.iter().enumerate() {
+				let mut attachment = json! {{ "id": i, "filename": file.filename }};
+				if let Some(ref description) = file.description {
+					attachment["description"] = json!(description);
+				}
+				attachments.push(attachment);
+			}
+		}
 	}
+
+	let payload = serde_json::to_string(&payload)?;
+	let files = files
+		.into_iter()
+		.enumerate()
+		.map(|(i, file)| (format!("files[{}]", i), file))
+		.collect();
+	multipart_form(vec![("payload_json", payload)], files)
+}
+
+/// Build a `multipart/form-data` body from text fields and named files.
+fn multipart_form(
+	fields: Vec<(&str, String)>,
+	files: Vec<(String, FileUpload)>,
+) -> Result<(hyper::header::ContentType, Vec<u8>)> {
+	use hyper::mime::{Attr, Mime, SubLevel, TopLevel, Value};
+
+	let cr = multipart::mock::ClientRequest::default();
+	let mut multi = multipart::client::Multipart::from_request(cr)?;
+	for (name, text) in fields {
+		multi.write_text(name, text)?;
+	}
+	for (name, mut file) in files {
+		multi.write_stream(name, &mut file.reader, Some(&file.filename), None)?;
+	}
+	let http_buffer: multipart::mock::HttpBuffer = multi.send()?;
+	let mime = Mime(
+		TopLevel::Multipart,
+		SubLevel::Ext("form-data".into()),
+		vec![(Attr::Ext("boundary".into()), Value::Ext(http_buffer.boundary))],
+	);
+	Ok((hyper::header::ContentType(mime), http_buffer.buf))
 }
 
 /// Argument to `get_messages` to specify the desired message retrieval.
+#[derive(Copy, Clone, Debug)]
 pub enum GetMessages {
 	/// Get the N most recent messages.
 	MostRecent,
@@ -1576,8 +2926,49 @@ pub enum GetMessages {
 	Around(MessageId),
 }
 
+/// Send a request, waiting beforehand if the rate limit for `url` is known to
+/// be exhausted, and retrying it as allowed by the `RetryPolicy`.
+fn rate_limited<'a, F: Fn() -> hyper::client::RequestBuilder<'a>>(
+	rate_limits: &RateLimits,
+	retry_policy: &RetryPolicy,
+	method: &str,
+	url: &str,
+	f: F,
+) -> Result<hyper::client::Response> {
+	let mut attempt = 1;
+	loop {
+		rate_limits.pre_check(method, url);
+		let result = retry(&f);
+		let rate_limited = match result {
+			Ok(ref response) => rate_limits.post_update(method, url, response),
+			Err(_) => false,
+		};
+		if !retry_policy.allows(attempt) {
+			return check_request_status(method, url, result);
+		}
+		if rate_limited {
+			if !retry_policy.retries_rate_limited() {
+				return check_request_status(method, url, result);
+			}
+			// pre_check waits until the rate limit allows the retry
+			debug!("Retrying after having been ratelimited");
+		} else if retry_policy.retries(method, &result) {
+			let delay = retry_policy.backoff(attempt);
+			debug!("Retrying in {:?} after a failed attempt", delay);
+			std::thread::sleep(delay);
+		} else {
+			return check_request_status(method, url, result);
+		}
+		attempt += 1;
+	}
+}
+
 /// Send a request with the correct `UserAgent`, retrying it a second time if the
 /// connection is aborted the first time.
+///
+/// This happens when a pooled connection has gone stale, before Discord
+/// receives anything, so it is done for every method and whatever the
+/// `RetryPolicy`.
 fn retry<'a, F: Fn() -> hyper::client::RequestBuilder<'a>>(
 	f: F,
 ) -> hyper::Result<hyper::client::Response> {
@@ -1596,13 +2987,17 @@ fn retry<'a, F: Fn() -> hyper::client::RequestBuilder<'a>>(
 	}
 }
 
-/// Convert non-success hyper statuses to discord crate errors, tossing info.
-fn check_status(
+/// Convert non-success hyper statuses to discord crate errors, recording the
+/// request in the error.
+fn check_request_status(
+	method: &str,
+	url: &str,
 	response: hyper::Result<hyper::client::Response>,
 ) -> Result<hyper::client::Response> {
 	let response: hyper::client::Response = response?;
 	if !response.status.is_success() {
-		return Err(Error::from_response(response));
+		let context = RequestContext::new(method, url, &response.headers);
+		return Err(Error::from_response(response, Some(context)));
 	}
 	Ok(response)
 }
@@ -1635,6 +3030,17 @@ fn resolve_invite(invite: &str) -> &str {
 	}
 }
 
+fn resolve_template(template: &str) -> &str {
+	let template = template
+		.trim_start_matches("https://")
+		.trim_start_matches("http://");
+	if template.starts_with("discord.new/") {
+		&template[12..]
+	} else {
+		template
+	}
+}
+
 fn sleep_ms(millis: u64) {
 	std::thread::sleep(time::Duration::from_millis(millis))
 }
@@ -1692,7 +3098,7 @@ trait SenderExt {
 	fn send_json(&mut self, value: &serde_json::Value) -> Result<()>;
 }
 
-impl ReceiverExt for websocket::client::Receiver<websocket::stream::WebSocketStream> {
+impl ReceiverExt for websocket::client::Receiver<proxy::WsStream> {
 	fn recv_json<F, T>(&mut self, decode: F) -> Result<T>
 	where
 		F: FnOnce(serde_json::Value) -> Result<T>,
@@ -1706,23 +3112,7 @@ impl ReceiverExt for websocket::client::Receiver<websocket::stream::WebSocketStr
 				String::from_utf8_lossy(&message.payload).into_owned(),
 			))
 		} else if message.opcode == Type::Binary || message.opcode == Type::Text {
-			let mut payload_vec;
-			let payload = if message.opcode == Type::Binary {
-				use std::io::Read;
-				payload_vec = Vec::new();
-				flate2::read::ZlibDecoder::new(&message.payload[..])
-					.read_to_end(&mut payload_vec)?;
-				&payload_vec[..]
-			} else {
-				&message.payload[..]
-			};
-			serde_json::from_reader(payload)
-				.map_err(From::from)
-				.and_then(decode)
-				.map_err(|e| {
-					warn!("Error decoding: {}", String::from_utf8_lossy(payload));
-					e
-				})
+			decode_json(&message.payload, message.opcode == Type::Binary, decode)
 		} else {
 			Err(Error::Closed(
 				None,
@@ -1732,7 +3122,31 @@ impl ReceiverExt for websocket::client::Receiver<websocket::stream::WebSocketStr
 	}
 }
 
-impl SenderExt for websocket::client::Sender<websocket::stream::WebSocketStream> {
+/// Decode a websocket message's payload, which is zlib-compressed if it was
+/// sent as binary.
+fn decode_json<F, T>(payload: &[u8], compressed: bool, decode: F) -> Result<T>
+where
+	F: FnOnce(serde_json::Value) -> Result<T>,
+{
+	let mut payload_vec;
+	let payload = if compressed {
+		use std::io::Read;
+		payload_vec = Vec::new();
+		flate2::read::ZlibDecoder::new(payload).read_to_end(&mut payload_vec)?;
+		&payload_vec[..]
+	} else {
+		payload
+	};
+	serde_json::from_reader(payload)
+		.map_err(From::from)
+		.and_then(decode)
+		.map_err(|e| {
+			warn!("Error decoding: {}", String::from_utf8_lossy(payload));
+			e
+		})
+}
+
+impl SenderExt for websocket::client::Sender<proxy::WsStream> {
 	fn send_json(&mut self, value: &serde_json::Value) -> Result<()> {
 		use websocket::message::Message;
 		use websocket::ws::sender::Sender;
@@ -1748,7 +3162,170 @@ mod internal {
 		SendMessage(::serde_json::Value),
 		Sequence(u64),
 		ChangeInterval(u64),
-		ChangeSender(::websocket::client::Sender<::websocket::stream::WebSocketStream>),
+		ChangeSender(::websocket::client::Sender<::proxy::WsStream>),
 		Aborted,
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use test_support::{mock_discord, mock_server, MESSAGE};
+
+	#[test]
+	#[allow(deprecated)]
+	fn custom_bases_and_transport() {
+		let (base, requests) = mock_server(
+			"200 OK",
+			r#"{"id":"1","username":"bot","discriminator":"0001","avatar":null,"email":null,"verified":true,"bot":true,"mfa_enabled":false}"#,
+		);
+		let user = mock_discord(&base).get_current_user().unwrap();
+		assert_eq!(user.id, UserId(1));
+		let request = requests.recv().unwrap();
+		assert!(request.starts_with("GET /api/users/@me HTTP/1.1\r\n"));
+		assert!(request.contains("Authorization: Bot token\r\n"));
+
+		let (base, requests) = mock_server("200 OK", r#"{"token":"abc"}"#);
+		let discord = DiscordBuilder::new()
+			.with_api_base(&base)
+			.with_transport(hyper::net::HttpConnector)
+			.login("a@b.c", "hunter2")
+			.unwrap();
+		assert_eq!(discord.token, "abc");
+		assert!(requests.recv().unwrap().starts_with("POST /api/auth/login HTTP/1.1\r\n"));
+
+		let (base, requests) = mock_server("200 OK", r#"{"incidents":[]}"#);
+		let incidents = DiscordBuilder::new()
+			.with_status_base(&base)
+			.with_transport(hyper::net::HttpConnector)
+			.get_unresolved_incidents()
+			.unwrap();
+		assert!(incidents.is_empty());
+		assert!(requests.recv().unwrap().starts_with("GET /api/incidents/unresolved.json HTTP/1.1\r\n"));
+	}
+
+	#[test]
+	fn query_strings() {
+		assert_eq!(with_query("/threads".into(), &[("before", None), ("limit", None)]), "/threads");
+		assert_eq!(
+			with_query("/threads".into(), &[("before", Some("2021-01-01T00:00:00+00:00".into())), ("limit", None)]),
+			"/threads?before=2021-01-01T00%3A00%3A00%2B00%3A00"
+		);
+		assert_eq!(with_query("/threads".into(), &[("before", None), ("limit", Some("2".into()))]), "/threads?limit=2");
+	}
+
+	#[test]
+	fn bulk_overwrite_server_commands() {
+		let (base, requests) = mock_server(
+			"200 OK",
+			r#"[{"id":"10","application_id":"1","guild_id":"7","name":"roll","description":"Roll dice","version":"11","options":[{"type":4,"name":"sides","description":"Sides","required":true,"choices":[{"name":"d6","value":6}]}]}]"#,
+		);
+		let commands = mock_discord(&base)
+			.bulk_overwrite_server_commands(ApplicationId(1), ServerId(7), |c| {
+				c.command(|c| {
+					c.name("roll").description("Roll dice").option(|o| {
+						o.kind(ApplicationCommandOptionType::Integer)
+							.name("sides")
+							.description("Sides")
+							.required(true)
+							.choice("d6", 6)
+					})
+				})
+			})
+			.unwrap();
+		assert_eq!(commands[0].id, CommandId(10));
+		assert_eq!(commands[0].kind, ApplicationCommandType::ChatInput);
+		assert_eq!(commands[0].options[0].choices[0].value, 6);
+		assert!(requests.recv().unwrap().contains(r#""choices":[{"name":"d6","value":6}]"#));
+
+		let entry_point: ApplicationCommand = serde_json::from_str(
+			r#"{"id":"12","type":4,"application_id":"1","name":"launch","description":"","version":"1"}"#,
+		)
+		.unwrap();
+		assert_eq!(entry_point.kind, ApplicationCommandType::Unknown(4));
+	}
+
+	#[test]
+	fn audit_log() {
+		let (base, requests) = mock_server(
+			"200 OK",
+			r#"{"audit_log_entries":[{"id":"9","user_id":"1","target_id":"5","action_type":22,"reason":"spam","changes":[]},{"id":"8","user_id":"1","target_id":"6","action_type":999,"changes":[{"key":"name","old_value":"a","new_value":"b"}]}],"users":[{"id":"1","username":"mod","discriminator":"0001","avatar":null}],"webhooks":[],"integrations":[{"id":"4","name":"Twitch","type":"twitch","account":{"id":"t","name":"streamer"}}],"threads":[],"application_commands":[]}"#,
+		);
+		let log = mock_discord(&base)
+			.get_audit_log(ServerId(3), |f| f.user(UserId(1)).action_type(AuditLogEvent::MemberBanAdd).limit(10))
+			.unwrap();
+		let request = requests.recv().unwrap();
+		assert!(request.starts_with("GET /api/guilds/3/audit-logs?action_type=22&limit=10&user_id=1 HTTP/1.1\r\n"));
+		assert_eq!(log.entries[0].action_type, AuditLogEvent::MemberBanAdd);
+		assert_eq!(log.entries[0].target_id, Some(5));
+		assert_eq!(log.entries[1].action_type, AuditLogEvent::Unknown(999));
+		assert_eq!(log.entries[1].changes[0].new_value, Some(json!("b")));
+		assert_eq!(log.users[0].name, "mod");
+		assert_eq!(log.integrations[0].account.name, "streamer");
+
+		let (base, requests) = mock_server("204 No Content", "");
+		mock_discord(&base)
+			.with_reason("Spam & ads, 2/3 strikes")
+			.kick_member(ServerId(3), UserId(4))
+			.unwrap();
+		assert!(requests.recv().unwrap().contains("X-Audit-Log-Reason: Spam%20%26%20ads%2C%202%2F3%20strikes\r\n"));
+	}
+
+	#[test]
+	fn message_files() {
+		let (base, requests) = mock_server("200 OK", MESSAGE);
+		let files = vec![
+			FileUpload::new("a.txt", &b"first"[..]).description("notes"),
+			FileUpload::new("b.png", &b"second"[..]).spoiler(),
+		];
+		mock_discord(&base)
+			.send_message_with_files(ChannelId(3), files, |m| m.content("hi").reply(MessageId(2), false))
+			.unwrap();
+		let request = requests.recv().unwrap();
+		assert!(request.contains("Content-Type: multipart/form-data; boundary="));
+		assert!(request.contains(r#""attachments":[{"description":"notes","filename":"a.txt","id":0},{"filename":"SPOILER_b.png","id":1}]"#));
+		assert!(request.contains("name=\"files[1]\"; filename=\"SPOILER_b.png\""));
+		assert!(request.contains("second"));
+
+		let (base, requests) = mock_server("200 OK", MESSAGE);
+		let kept: Attachment = serde_json::from_str(
+			r#"{"id":"7","filename":"old.txt","url":"u","proxy_url":"p","size":3}"#,
+		)
+		.unwrap();
+		let files = vec![FileUpload::new("new.txt", &b"new"[..])];
+		mock_discord(&base)
+			.edit_message_with_files(ChannelId(3), MessageId(2), files, |m| m.keep_attachment(&kept))
+			.unwrap();
+		assert!(requests.recv().unwrap().contains(r#"{"attachments":[{"id":"7"},{"filename":"new.txt","id":0}]}"#));
+	}
+
+	#[test]
+	fn server_from_template() {
+		let (base, requests) = mock_server(
+			"201 Created",
+			r#"{"id":"4","name":"Club","afk_timeout":300,"afk_channel_id":null,"icon":null,"roles":[],
+				"region":"us-west","embed_enabled":false,"embed_channel_id":null,"owner_id":"1",
+				"verification_level":0,"emojis":[],"features":[],"splash":null,
+				"default_message_notifications":0,"mfa_level":0}"#,
+		);
+		let server = mock_discord(&base)
+			.create_server_from_template("https://discord.new/hgM48av5Q69A", "Club", None)
+			.unwrap();
+		assert_eq!(server.id, ServerId(4));
+		assert!(requests.recv().unwrap().starts_with("POST /api/guilds/templates/hgM48av5Q69A "));
+	}
+
+	#[test]
+	fn download_cdn() {
+		let (base, requests) = mock_server("200 OK", "GIF89a");
+		let discord = DiscordBuilder::new()
+			.with_cdn_base(&base)
+			.with_transport(hyper::net::HttpConnector)
+			.from_bot_token("token")
+			.unwrap();
+		assert_eq!(discord.get_user_avatar(UserId(1), "a_abc").unwrap(), b"GIF89a");
+		let request = requests.recv().unwrap();
+		assert!(request.starts_with("GET /api/avatars/1/a_abc.gif HTTP/1.1\r\n"));
+		assert!(!request.contains("Authorization"));
+	}
+}
@@ -10,6 +10,7 @@ use std::str::FromStr;
 use serde_json::Value;
 
 use super::{Error, Object, Result};
+use cdn::{self, ImageUrl};
 
 use chrono::prelude::*;
 
@@ -141,6 +142,22 @@ id! {
 	RoleId;
 	/// An identifier for an Emoji
 	EmojiId;
+	/// An identifier for a Webhook
+	WebhookId;
+	/// An identifier for an application Command
+	CommandId;
+	/// An identifier for an Interaction
+	InteractionId;
+	/// An identifier for an entry in a server's audit log
+	AuditLogEntryId;
+	/// An identifier for a server Integration
+	IntegrationId;
+	/// An identifier for a ScheduledEvent
+	ScheduledEventId;
+	/// An identifier for a StageInstance
+	StageInstanceId;
+	/// An identifier for a Sticker
+	StickerId;
 }
 
 impl ServerId {
@@ -324,9 +341,12 @@ impl ServerInfo {
 	///
 	/// Returns None if the server does not have an icon.
 	pub fn icon_url(&self) -> Option<String> {
-		self.icon
-			.as_ref()
-			.map(|icon| format!(cdn_concat!("/icons/{}/{}.jpg"), self.id, icon))
+		self.icon_image().map(String::from)
+	}
+
+	/// Returns the server's icon, which can be resized or converted.
+	pub fn icon_image(&self) -> Option<ImageUrl> {
+		self.icon.as_ref().map(|icon| cdn::server_icon(self.id, icon))
 	}
 }
 
@@ -357,9 +377,19 @@ impl Server {
 	///
 	/// Returns None if the server does not have an icon.
 	pub fn icon_url(&self) -> Option<String> {
-		self.icon
+		self.icon_image().map(String::from)
+	}
+
+	/// Returns the server's icon, which can be resized or converted.
+	pub fn icon_image(&self) -> Option<ImageUrl> {
+		self.icon.as_ref().map(|icon| cdn::server_icon(self.id, icon))
+	}
+
+	/// Returns the formatted URL of the server's invite splash image.
+	pub fn splash_url(&self) -> Option<String> {
+		self.splash
 			.as_ref()
-			.map(|icon| format!(cdn_concat!("/icons/{}/{}.jpg"), self.id, icon))
+			.map(|splash| cdn::server_splash(self.id, splash).into())
 	}
 }
 
@@ -398,8 +428,8 @@ impl Role {
 /// A banning of a user
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ban {
-	reason: Option<String>,
-	user: User,
+	pub reason: Option<String>,
+	pub user: User,
 }
 serial_decode!(Ban);
 
@@ -428,9 +458,24 @@ impl User {
 	///
 	/// Returns None if the user does not have an avatar.
 	pub fn avatar_url(&self) -> Option<String> {
-		self.avatar
-			.as_ref()
-			.map(|av| format!(cdn_concat!("/avatars/{}/{}.jpg"), self.id, av))
+		self.avatar_image().map(String::from)
+	}
+
+	/// Returns the user's avatar, which can be resized or converted.
+	pub fn avatar_image(&self) -> Option<ImageUrl> {
+		self.avatar.as_ref().map(|av| cdn::user_avatar(self.id, av))
+	}
+
+	/// Returns the formatted URL of the avatar shown for the user if they
+	/// have not set one.
+	pub fn default_avatar_url(&self) -> String {
+		cdn::default_avatar(self.id, self.discriminator).into()
+	}
+
+	/// Returns the formatted URL of the user's avatar, or of their default
+	/// avatar if they have not set one.
+	pub fn face(&self) -> String {
+		self.avatar_url().unwrap_or_else(|| self.default_avatar_url())
 	}
 }
 
@@ -464,7 +509,7 @@ pub enum Channel {
 	Group(Group),
 	/// Text channel to another user
 	Private(PrivateChannel),
-	/// Voice or text channel within a server
+	/// Voice, text or thread channel within a server
 	Public(PublicChannel),
 	/// an organizational category that contains channels
 	Category(ChannelCategory),
@@ -479,7 +524,7 @@ impl Channel {
 		let map = try!(into_map(value));
 		// https://discord.com/developers/docs/resources/channel#channel-object-channel-types
 		match req!(map.get("type").and_then(|x| x.as_u64())) {
-			0 | 2 => PublicChannel::decode(Value::Object(map)).map(Channel::Public),
+			0 | 2 | 10 | 11 | 12 | 13 => PublicChannel::decode(Value::Object(map)).map(Channel::Public),
 			1 => PrivateChannel::decode(Value::Object(map)).map(Channel::Private),
 			3 => Group::decode(Value::Object(map)).map(Channel::Group),
 			4 => ChannelCategory::decode(Value::Object(map)).map(Channel::Category),
@@ -536,12 +581,14 @@ impl Group {
 	///
 	/// Returns None if the group does not have an icon.
 	pub fn icon_url(&self) -> Option<String> {
-		self.icon.as_ref().map(|icon| {
-			format!(
-				cdn_concat!("/channel-icons/{}/{}.jpg"),
-				self.channel_id, icon
-			)
-		})
+		self.icon_image().map(String::from)
+	}
+
+	/// Returns the group's icon, which can be resized or converted.
+	pub fn icon_image(&self) -> Option<ImageUrl> {
+		self.icon
+			.as_ref()
+			.map(|icon| cdn::group_icon(self.channel_id, icon))
 	}
 }
 
@@ -599,7 +646,7 @@ impl PrivateChannel {
 	}
 }
 
-/// Public voice or text channel within a server
+/// Public voice, text or thread channel within a server
 #[derive(Debug, Clone)]
 pub struct PublicChannel {
 	pub id: ChannelId,
@@ -614,7 +661,18 @@ pub struct PublicChannel {
 	pub user_limit: Option<u64>,
 	pub last_pin_timestamp: Option<DateTime<FixedOffset>>,
 	pub nsfw: bool,
+	/// The category of a channel, or the parent channel of a thread
 	pub parent_id: Option<ChannelId>,
+	/// Thread-specific fields, present only for threads
+	pub thread_metadata: Option<ThreadMetadata>,
+	/// The creator of a thread
+	pub owner_id: Option<UserId>,
+	/// An approximate count of messages in a thread, stopping at 50
+	pub message_count: Option<u64>,
+	/// An approximate count of users in a thread, stopping at 50
+	pub member_count: Option<u64>,
+	/// The current user's membership of a thread, if they have joined it
+	pub member: Option<ThreadMember>,
 }
 
 impl PublicChannel {
@@ -633,13 +691,14 @@ impl PublicChannel {
 				name: try!(remove(&mut value, "name").and_then(into_string)),
 				server_id: server_id,
 				topic: try!(opt(&mut value, "topic", into_string)),
-				position: req!(try!(remove(&mut value, "position")).as_i64()),
+				// threads have neither a position nor overwrites of their own
+				position: try!(opt(&mut value, "position", |v| Ok(req!(v.as_i64())))).unwrap_or(0),
 				kind: try!(remove(&mut value, "type").and_then(serde)),
 				last_message_id: try!(opt(&mut value, "last_message_id", MessageId::decode)),
-				permission_overwrites: try!(decode_array(
-					try!(remove(&mut value, "permission_overwrites")),
-					PermissionOverwrite::decode
-				)),
+				permission_overwrites: try!(opt(&mut value, "permission_overwrites", |v| {
+					decode_array(v, PermissionOverwrite::decode)
+				}))
+				.unwrap_or_default(),
 				bitrate: remove(&mut value, "bitrate").ok().and_then(|v| v.as_u64()),
 				user_limit: remove(&mut value, "user_limit")
 					.ok()
@@ -647,10 +706,23 @@ impl PublicChannel {
 				last_pin_timestamp: try!(opt(&mut value, "last_pin_timestamp", into_timestamp)),
 				nsfw: try!(opt(&mut value, "nsfw", |v| Ok(req!(v.as_bool())))).unwrap_or(false),
 				parent_id: try!(opt(&mut value, "parent_id", ChannelId::decode)),
+				thread_metadata: try!(opt(&mut value, "thread_metadata", ThreadMetadata::decode)),
+				owner_id: try!(opt(&mut value, "owner_id", UserId::decode)),
+				message_count: try!(opt(&mut value, "message_count", |v| Ok(req!(v.as_u64())))),
+				member_count: try!(opt(&mut value, "member_count", |v| Ok(req!(v.as_u64())))),
+				member: try!(opt(&mut value, "member", ThreadMember::decode)),
 			}
 		)
 	}
 
+	/// Check whether this channel is a thread.
+	pub fn is_thread(&self) -> bool {
+		match self.kind {
+			ChannelType::NewsThread | ChannelType::PublicThread | ChannelType::PrivateThread => true,
+			_ => false,
+		}
+	}
+
 	/// Return a `Mention` which will link to this channel.
 	#[inline(always)]
 	pub fn mention(&self) -> Mention {
@@ -658,6 +730,63 @@ impl PublicChannel {
 	}
 }
 
+/// Thread-specific fields of a `PublicChannel`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadMetadata {
+	pub archived: bool,
+	/// Minutes of inactivity after which the thread is archived: 60, 1440,
+	/// 4320 or 10080
+	pub auto_archive_duration: u64,
+	/// When the thread's archive status was last changed
+	pub archive_timestamp: DateTime<FixedOffset>,
+	/// Whether only moderators can unarchive the thread
+	#[serde(default)]
+	pub locked: bool,
+	/// Whether non-moderators can add members to a private thread
+	pub invitable: Option<bool>,
+	/// When the thread was created, for threads created after 2022-01-09
+	pub create_timestamp: Option<DateTime<FixedOffset>>,
+}
+serial_decode!(ThreadMetadata);
+
+/// A user's membership of a thread
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThreadMember {
+	/// The thread, omitted within a `PublicChannel`
+	#[serde(rename = "id")]
+	pub thread_id: Option<ChannelId>,
+	/// The user, omitted within a `PublicChannel`
+	pub user_id: Option<UserId>,
+	pub join_timestamp: DateTime<FixedOffset>,
+	/// Notification settings
+	pub flags: u64,
+}
+serial_decode!(ThreadMember);
+
+/// A list of threads and the current user's memberships of them
+#[derive(Debug, Clone)]
+pub struct ThreadList {
+	pub threads: Vec<PublicChannel>,
+	/// Thread members for the threads the current user has joined
+	pub members: Vec<ThreadMember>,
+	/// Whether more archived threads can be fetched
+	pub has_more: bool,
+}
+
+impl ThreadList {
+	pub fn decode(value: Value) -> Result<ThreadList> {
+		let mut value = try!(into_map(value));
+		warn_json!(
+			value,
+			ThreadList {
+				threads: try!(remove(&mut value, "threads").and_then(|v| decode_array(v, PublicChannel::decode))),
+				members: try!(remove(&mut value, "members").and_then(|v| decode_array(v, ThreadMember::decode))),
+				has_more: try!(opt(&mut value, "has_more", |v| Ok(req!(v.as_bool())))).unwrap_or(false),
+			}
+		)
+	}
+}
+
 /// The type of edit being made to a Channel's permissions.
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub enum PermissionOverwriteType {
@@ -746,6 +875,10 @@ bitflags! {
 		const VOICE_MOVE_MEMBERS = 1 << 24;
 		/// When denied, members must use push-to-talk
 		const VOICE_USE_VAD = 1 << 25;
+		/// Ask to speak in stage channels
+		const REQUEST_TO_SPEAK = 1 << 32;
+		/// Create, edit and delete scheduled events
+		const MANAGE_EVENTS = 1 << 33;
 	}
 }
 
@@ -839,6 +972,8 @@ bitflags! {
 		const SUPPRESS_EMBEDS = 1 << 2;
 		const SOURCE_MESSAGE_DELETED = 1 << 3;
 		const URGENT = 1 << 4;
+		/// Only visible to the user who used an interaction
+		const EPHEMERAL = 1 << 6;
 	}
 }
 
@@ -872,6 +1007,11 @@ pub struct Message {
 	/// Follows OEmbed standard
 	pub embeds: Vec<Value>,
 	pub referenced_message: Option<Box<Message>>,
+	/// Action rows of buttons and select menus
+	#[serde(default)]
+	pub components: Vec<Component>,
+	#[serde(default)]
+	pub sticker_items: Vec<StickerItem>,
 
 	pub flags: MessageFlags,
 }
@@ -1054,6 +1194,1039 @@ pub struct VoiceRegion {
 }
 serial_decode!(VoiceRegion);
 
+/// The type of a webhook
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum WebhookType {
+	/// A webhook which posts messages to a channel using its token
+	Incoming,
+	/// An internal webhook used to crosspost from followed news channels
+	ChannelFollower,
+	/// A webhook used by an application for interactions
+	Application,
+	/// A webhook type not covered by the above
+	Unknown(u64),
+}
+
+serial_use_mapping!(WebhookType, numeric);
+serial_numbers! { WebhookType(Unknown);
+	Incoming, 1;
+	ChannelFollower, 2;
+	Application, 3;
+}
+
+/// A webhook which can post messages to a channel
+/// https://discord.com/developers/docs/resources/webhook#webhook-object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+	pub id: WebhookId,
+	#[serde(rename = "type")]
+	pub kind: WebhookType,
+	#[serde(rename = "guild_id")]
+	pub server_id: Option<ServerId>,
+	pub channel_id: Option<ChannelId>,
+	/// The user who created the webhook, absent when fetched by token
+	pub user: Option<User>,
+	pub name: Option<String>,
+	pub avatar: Option<String>,
+	/// The secure token, present only on incoming webhooks
+	pub token: Option<String>,
+	pub application_id: Option<ApplicationId>,
+	/// The URL used for executing the webhook
+	pub url: Option<String>,
+}
+serial_decode!(Webhook);
+
+impl Webhook {
+	/// Returns the formatted URL of the webhook's avatar.
+	///
+	/// Returns None if the webhook does not have an avatar.
+	pub fn avatar_url(&self) -> Option<String> {
+		self.avatar_image().map(String::from)
+	}
+
+	/// Returns the webhook's avatar, which can be resized or converted.
+	pub fn avatar_image(&self) -> Option<ImageUrl> {
+		self.avatar.as_ref().map(|av| cdn::webhook_avatar(self.id, av))
+	}
+}
+
+/// An event scheduled on a server
+/// https://discord.com/developers/docs/resources/guild-scheduled-event#guild-scheduled-event-object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledEvent {
+	pub id: ScheduledEventId,
+	#[serde(rename = "guild_id")]
+	pub server_id: ServerId,
+	/// The stage or voice channel of the event, or `None` for external events
+	pub channel_id: Option<ChannelId>,
+	/// The user who created the event, for events created after 2021-10-25
+	pub creator_id: Option<UserId>,
+	pub name: String,
+	pub description: Option<String>,
+	pub scheduled_start_time: DateTime<FixedOffset>,
+	/// Required for external events
+	pub scheduled_end_time: Option<DateTime<FixedOffset>>,
+	pub privacy_level: ScheduledEventPrivacyLevel,
+	pub status: ScheduledEventStatus,
+	pub entity_type: ScheduledEventEntityType,
+	/// The stage instance of an event in a stage channel
+	#[serde(default)]
+	#[serde(deserialize_with = "::serial::deserialize_id_opt")]
+	pub entity_id: Option<u64>,
+	pub entity_metadata: Option<ScheduledEventMetadata>,
+	pub creator: Option<User>,
+	/// The number of subscribed users, if requested with `with_user_count`
+	pub user_count: Option<u64>,
+	/// The cover image hash
+	pub image: Option<String>,
+}
+serial_decode!(ScheduledEvent);
+
+impl ScheduledEvent {
+	/// Returns the formatted URL of the event's cover image.
+	pub fn image_url(&self) -> Option<String> {
+		self.image
+			.as_ref()
+			.map(|image| cdn::scheduled_event_cover(self.id, image).into())
+	}
+}
+
+/// Additional information about a `ScheduledEvent`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledEventMetadata {
+	/// The location of an external event, of 1-100 characters
+	pub location: Option<String>,
+}
+
+/// Who can see a `ScheduledEvent`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum ScheduledEventPrivacyLevel {
+	/// Only members of the server
+	ServerOnly,
+	/// A privacy level not covered by the above
+	Unknown(u64),
+}
+
+serial_use_mapping!(ScheduledEventPrivacyLevel, numeric);
+serial_numbers! { ScheduledEventPrivacyLevel(Unknown);
+	ServerOnly, 2;
+}
+
+/// The progress of a `ScheduledEvent`
+///
+/// A scheduled event may become active or canceled, and an active event may
+/// only become completed.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum ScheduledEventStatus {
+	Scheduled,
+	Active,
+	Completed,
+	Canceled,
+	/// A status not covered by the above
+	Unknown(u64),
+}
+
+serial_use_mapping!(ScheduledEventStatus, numeric);
+serial_numbers! { ScheduledEventStatus(Unknown);
+	Scheduled, 1;
+	Active, 2;
+	Completed, 3;
+	Canceled, 4;
+}
+
+/// Where a `ScheduledEvent` takes place
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum ScheduledEventEntityType {
+	StageInstance,
+	Voice,
+	/// Somewhere outside Discord, described by its `location`
+	External,
+	/// An entity type not covered by the above
+	Unknown(u64),
+}
+
+serial_use_mapping!(ScheduledEventEntityType, numeric);
+serial_numbers! { ScheduledEventEntityType(Unknown);
+	StageInstance, 1;
+	Voice, 2;
+	External, 3;
+}
+
+/// A user subscribed to a `ScheduledEvent`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledEventUser {
+	#[serde(rename = "guild_scheduled_event_id")]
+	pub event_id: ScheduledEventId,
+	pub user: User,
+	/// The user's membership of the server, if requested with `with_member`
+	pub member: Option<Member>,
+}
+serial_decode!(ScheduledEventUser);
+
+/// A live stage in a stage channel
+/// https://discord.com/developers/docs/resources/stage-instance#stage-instance-object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StageInstance {
+	pub id: StageInstanceId,
+	#[serde(rename = "guild_id")]
+	pub server_id: ServerId,
+	pub channel_id: ChannelId,
+	/// The topic of the stage, of 1-120 characters
+	pub topic: String,
+	pub privacy_level: StagePrivacyLevel,
+	/// The scheduled event the stage was started for, if any
+	#[serde(rename = "guild_scheduled_event_id")]
+	pub scheduled_event_id: Option<ScheduledEventId>,
+	#[serde(default)]
+	pub discoverable_disabled: bool,
+}
+serial_decode!(StageInstance);
+
+/// Who can see a `StageInstance`
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum StagePrivacyLevel {
+	/// Visible publicly; no longer supported
+	Public,
+	/// Only members of the server
+	ServerOnly,
+	/// A privacy level not covered by the above
+	Unknown(u64),
+}
+
+serial_use_mapping!(StagePrivacyLevel, numeric);
+serial_numbers! { StagePrivacyLevel(Unknown);
+	Public, 1;
+	ServerOnly, 2;
+}
+
+/// A template from which new servers can be created
+/// https://discord.com/developers/docs/resources/guild-template#guild-template-object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerTemplate {
+	/// The code identifying the template, as in `https://discord.new/CODE`
+	pub code: String,
+	pub name: String,
+	pub description: Option<String>,
+	/// The number of servers created from the template
+	pub usage_count: u64,
+	pub creator_id: UserId,
+	pub creator: User,
+	pub created_at: DateTime<FixedOffset>,
+	/// When the template was last synced with its source server
+	pub updated_at: DateTime<FixedOffset>,
+	#[serde(rename = "source_guild_id")]
+	pub source_server_id: ServerId,
+	/// A snapshot of the source server's settings, roles and channels
+	#[serde(rename = "serialized_source_guild")]
+	pub serialized_source_server: Value,
+	/// Whether the source server has changed since the template was synced
+	#[serde(rename = "is_dirty")]
+	pub dirty: Option<bool>,
+}
+serial_decode!(ServerTemplate);
+
+/// A server's audit log, with the objects its entries refer to
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+	/// Entries, most recent first
+	pub entries: Vec<AuditLogEntry>,
+	pub users: Vec<User>,
+	pub webhooks: Vec<Webhook>,
+	pub integrations: Vec<PartialIntegration>,
+	pub threads: Vec<PublicChannel>,
+	pub scheduled_events: Vec<ScheduledEvent>,
+}
+
+impl AuditLog {
+	pub fn decode(value: Value) -> Result<AuditLog> {
+		let mut value = try!(into_map(value));
+		// application commands and automod rules are not kept
+		value.remove("application_commands");
+		value.remove("auto_moderation_rules");
+		warn_json!(
+			value,
+			AuditLog {
+				entries: try!(remove(&mut value, "audit_log_entries").and_then(|v| decode_array(v, AuditLogEntry::decode))),
+				users: try!(opt(&mut value, "users", |v| decode_array(v, User::decode))).unwrap_or_default(),
+				webhooks: try!(opt(&mut value, "webhooks", |v| decode_array(v, Webhook::decode))).unwrap_or_default(),
+				integrations: try!(opt(&mut value, "integrations", |v| decode_array(v, PartialIntegration::decode)))
+					.unwrap_or_default(),
+				threads: try!(opt(&mut value, "threads", |v| decode_array(v, PublicChannel::decode))).unwrap_or_default(),
+				scheduled_events: try!(opt(&mut value, "guild_scheduled_events", |v| decode_array(
+					v,
+					ScheduledEvent::decode
+				)))
+				.unwrap_or_default(),
+			}
+		)
+	}
+}
+
+/// A single administrative action recorded in an `AuditLog`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+	pub id: AuditLogEntryId,
+	pub action_type: AuditLogEvent,
+	/// The user who made the changes
+	pub user_id: Option<UserId>,
+	/// The ID of the affected channel, user, role or other object
+	#[serde(default)]
+	#[serde(deserialize_with = "::serial::deserialize_id_opt")]
+	pub target_id: Option<u64>,
+	#[serde(default)]
+	pub changes: Vec<AuditLogChange>,
+	/// Additional information for certain action types
+	pub options: Option<AuditLogOptions>,
+	pub reason: Option<String>,
+}
+serial_decode!(AuditLogEntry);
+
+/// A change to one field of the object affected by an `AuditLogEntry`
+///
+/// See the [docs] for the meaning of each key.
+/// [docs]: https://discord.com/developers/docs/resources/audit-log#audit-log-change-object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogChange {
+	/// The name of the changed field, like `"name"` or `"$add"` for roles
+	pub key: String,
+	pub old_value: Option<Value>,
+	pub new_value: Option<Value>,
+}
+serial_decode!(AuditLogChange);
+
+/// Additional information about certain `AuditLogEntry` action types
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditLogOptions {
+	/// The channel of moved members, deleted or pinned messages, or stage
+	/// instances
+	pub channel_id: Option<ChannelId>,
+	/// The number of entities affected
+	pub count: Option<String>,
+	/// The number of days after which inactive members were pruned
+	pub delete_member_days: Option<String>,
+	/// The number of members removed by a prune
+	pub members_removed: Option<String>,
+	/// The pinned or unpinned message
+	pub message_id: Option<MessageId>,
+	/// The role or user of a changed permission overwrite
+	#[serde(default)]
+	#[serde(deserialize_with = "::serial::deserialize_id_opt")]
+	pub id: Option<u64>,
+	/// The type of a changed permission overwrite: `"0"` for a role or `"1"`
+	/// for a member
+	#[serde(rename = "type")]
+	pub kind: Option<String>,
+	/// The name of a role in a changed permission overwrite
+	pub role_name: Option<String>,
+}
+
+/// The type of action recorded in an `AuditLogEntry`
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum AuditLogEvent {
+	ServerUpdate,
+	ChannelCreate,
+	ChannelUpdate,
+	ChannelDelete,
+	ChannelOverwriteCreate,
+	ChannelOverwriteUpdate,
+	ChannelOverwriteDelete,
+	MemberKick,
+	MemberPrune,
+	MemberBanAdd,
+	MemberBanRemove,
+	MemberUpdate,
+	MemberRoleUpdate,
+	MemberMove,
+	MemberDisconnect,
+	BotAdd,
+	RoleCreate,
+	RoleUpdate,
+	RoleDelete,
+	InviteCreate,
+	InviteUpdate,
+	InviteDelete,
+	WebhookCreate,
+	WebhookUpdate,
+	WebhookDelete,
+	EmojiCreate,
+	EmojiUpdate,
+	EmojiDelete,
+	MessageDelete,
+	MessageBulkDelete,
+	MessagePin,
+	MessageUnpin,
+	IntegrationCreate,
+	IntegrationUpdate,
+	IntegrationDelete,
+	StageInstanceCreate,
+	StageInstanceUpdate,
+	StageInstanceDelete,
+	StickerCreate,
+	StickerUpdate,
+	StickerDelete,
+	ScheduledEventCreate,
+	ScheduledEventUpdate,
+	ScheduledEventDelete,
+	ThreadCreate,
+	ThreadUpdate,
+	ThreadDelete,
+	ApplicationCommandPermissionUpdate,
+	AutoModerationRuleCreate,
+	AutoModerationRuleUpdate,
+	AutoModerationRuleDelete,
+	AutoModerationBlockMessage,
+	AutoModerationFlagToChannel,
+	AutoModerationUserCommunicationDisabled,
+	/// An action type not covered by the above
+	Unknown(u64),
+}
+
+serial_use_mapping!(AuditLogEvent, numeric);
+serial_numbers! { AuditLogEvent(Unknown);
+	ServerUpdate, 1;
+	ChannelCreate, 10;
+	ChannelUpdate, 11;
+	ChannelDelete, 12;
+	ChannelOverwriteCreate, 13;
+	ChannelOverwriteUpdate, 14;
+	ChannelOverwriteDelete, 15;
+	MemberKick, 20;
+	MemberPrune, 21;
+	MemberBanAdd, 22;
+	MemberBanRemove, 23;
+	MemberUpdate, 24;
+	MemberRoleUpdate, 25;
+	MemberMove, 26;
+	MemberDisconnect, 27;
+	BotAdd, 28;
+	RoleCreate, 30;
+	RoleUpdate, 31;
+	RoleDelete, 32;
+	InviteCreate, 40;
+	InviteUpdate, 41;
+	InviteDelete, 42;
+	WebhookCreate, 50;
+	WebhookUpdate, 51;
+	WebhookDelete, 52;
+	EmojiCreate, 60;
+	EmojiUpdate, 61;
+	EmojiDelete, 62;
+	MessageDelete, 72;
+	MessageBulkDelete, 73;
+	MessagePin, 74;
+	MessageUnpin, 75;
+	IntegrationCreate, 80;
+	IntegrationUpdate, 81;
+	IntegrationDelete, 82;
+	StageInstanceCreate, 83;
+	StageInstanceUpdate, 84;
+	StageInstanceDelete, 85;
+	StickerCreate, 90;
+	StickerUpdate, 91;
+	StickerDelete, 92;
+	ScheduledEventCreate, 100;
+	ScheduledEventUpdate, 101;
+	ScheduledEventDelete, 102;
+	ThreadCreate, 110;
+	ThreadUpdate, 111;
+	ThreadDelete, 112;
+	ApplicationCommandPermissionUpdate, 121;
+	AutoModerationRuleCreate, 140;
+	AutoModerationRuleUpdate, 141;
+	AutoModerationRuleDelete, 142;
+	AutoModerationBlockMessage, 143;
+	AutoModerationFlagToChannel, 144;
+	AutoModerationUserCommunicationDisabled, 145;
+}
+
+/// An integration as referenced by an `AuditLog`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialIntegration {
+	pub id: IntegrationId,
+	pub name: String,
+	/// `"twitch"`, `"youtube"`, `"discord"` or `"guild_subscription"`
+	#[serde(rename = "type")]
+	pub kind: String,
+	pub account: IntegrationAccount,
+}
+serial_decode!(PartialIntegration);
+
+/// The external account of an integration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrationAccount {
+	pub id: String,
+	pub name: String,
+}
+
+/// A connection between a server and Twitch, YouTube or a bot application
+/// https://discord.com/developers/docs/resources/guild#integration-object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Integration {
+	pub id: IntegrationId,
+	pub name: String,
+	/// `"twitch"`, `"youtube"`, `"discord"` or `"guild_subscription"`
+	#[serde(rename = "type")]
+	pub kind: String,
+	pub enabled: bool,
+	pub account: IntegrationAccount,
+	/// Whether the integration is syncing subscribers, for Twitch and YouTube
+	pub syncing: Option<bool>,
+	/// The role given to subscribers
+	pub role_id: Option<RoleId>,
+	/// Whether emoticons are synced, for Twitch
+	pub enable_emoticons: Option<bool>,
+	/// What happens to subscribers whose subscription expires
+	pub expire_behavior: Option<IntegrationExpireBehavior>,
+	/// Days of grace after a subscription expires
+	pub expire_grace_period: Option<u64>,
+	/// The user who added the integration
+	pub user: Option<User>,
+	pub synced_at: Option<DateTime<FixedOffset>>,
+	pub subscriber_count: Option<u64>,
+	/// Whether the integration has been revoked
+	pub revoked: Option<bool>,
+	/// The bot application of a `"discord"` integration
+	pub application: Option<IntegrationApplication>,
+}
+serial_decode!(Integration);
+
+/// What happens to a subscriber of an `Integration` when their subscription
+/// expires
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum IntegrationExpireBehavior {
+	RemoveRole,
+	Kick,
+	/// A behavior not covered by the above
+	Unknown(u64),
+}
+
+serial_use_mapping!(IntegrationExpireBehavior, numeric);
+serial_numbers! { IntegrationExpireBehavior(Unknown);
+	RemoveRole, 0;
+	Kick, 1;
+}
+
+/// The bot application of an `Integration`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrationApplication {
+	pub id: ApplicationId,
+	pub name: String,
+	pub icon: Option<String>,
+	pub description: String,
+	pub bot: Option<User>,
+}
+
+impl IntegrationApplication {
+	/// Returns the formatted URL of the application's icon.
+	///
+	/// Returns None if the application does not have an icon.
+	pub fn icon_url(&self) -> Option<String> {
+		self.icon_image().map(String::from)
+	}
+
+	/// Returns the application's icon, which can be resized or converted.
+	pub fn icon_image(&self) -> Option<ImageUrl> {
+		self.icon.as_ref().map(|icon| cdn::application_icon(self.id, icon))
+	}
+}
+
+/// The type of an application command
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum ApplicationCommandType {
+	/// A slash command, typed into the message box
+	ChatInput,
+	/// A command shown in the context menu of a user
+	User,
+	/// A command shown in the context menu of a message
+	Message,
+	/// A command type not covered by the above, such as an activity's
+	/// entry point
+	Unknown(u64),
+}
+
+serial_use_mapping!(ApplicationCommandType, numeric);
+serial_numbers! { ApplicationCommandType(Unknown);
+	ChatInput, 1;
+	User, 2;
+	Message, 3;
+}
+
+/// The type of an application command option
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum ApplicationCommandOptionType {
+	SubCommand,
+	SubCommandGroup,
+	String,
+	/// Any integer between -2^53 and 2^53
+	Integer,
+	Boolean,
+	User,
+	/// Any channel type, or those listed in `channel_types`
+	Channel,
+	Role,
+	/// A user or a role
+	Mentionable,
+	/// Any double between -2^53 and 2^53
+	Number,
+	Attachment,
+}
+
+serial_use_mapping!(ApplicationCommandOptionType, numeric);
+serial_numbers! { ApplicationCommandOptionType;
+	SubCommand, 1;
+	SubCommandGroup, 2;
+	String, 3;
+	Integer, 4;
+	Boolean, 5;
+	User, 6;
+	Channel, 7;
+	Role, 8;
+	Mentionable, 9;
+	Number, 10;
+	Attachment, 11;
+}
+
+/// A predefined value which the user may pick for an option
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplicationCommandOptionChoice {
+	pub name: String,
+	/// A string, integer, or number, depending on the option's type
+	pub value: Value,
+}
+serial_decode!(ApplicationCommandOptionChoice);
+
+/// A parameter of an application command, or one of its subcommands
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplicationCommandOption {
+	#[serde(rename = "type")]
+	pub kind: ApplicationCommandOptionType,
+	pub name: String,
+	pub description: String,
+	#[serde(default)]
+	pub required: bool,
+	#[serde(default)]
+	pub choices: Vec<ApplicationCommandOptionChoice>,
+	/// Parameters of a subcommand or subcommand group
+	#[serde(default)]
+	pub options: Vec<ApplicationCommandOption>,
+	#[serde(default)]
+	pub channel_types: Vec<ChannelType>,
+	pub min_value: Option<Value>,
+	pub max_value: Option<Value>,
+	#[serde(default)]
+	pub autocomplete: bool,
+}
+serial_decode!(ApplicationCommandOption);
+
+/// A command registered by an application, globally or for a single server
+/// https://discord.com/developers/docs/interactions/application-commands#application-command-object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApplicationCommand {
+	pub id: CommandId,
+	#[serde(rename = "type")]
+	#[serde(default)]
+	pub kind: ApplicationCommandType,
+	pub application_id: ApplicationId,
+	/// The server of a server command, `None` for global commands
+	#[serde(rename = "guild_id")]
+	pub server_id: Option<ServerId>,
+	pub name: String,
+	pub description: String,
+	#[serde(default)]
+	pub options: Vec<ApplicationCommandOption>,
+	/// The permissions required to use the command by default, as a string
+	pub default_member_permissions: Option<String>,
+	/// Whether the command is available in private channels
+	pub dm_permission: Option<bool>,
+	/// Autoincrementing version identifier updated on substantial changes
+	pub version: CommandId,
+}
+serial_decode!(ApplicationCommand);
+
+impl Default for ApplicationCommandType {
+	fn default() -> Self {
+		ApplicationCommandType::ChatInput
+	}
+}
+
+/// The type of an interaction
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum InteractionType {
+	/// Sent when validating an HTTP interactions endpoint
+	Ping,
+	/// An application command was used
+	ApplicationCommand,
+	/// A button was clicked or a select menu option chosen
+	MessageComponent,
+	/// An option with `autocomplete` set is being filled in
+	ApplicationCommandAutocomplete,
+	/// A modal was submitted
+	ModalSubmit,
+}
+
+serial_use_mapping!(InteractionType, numeric);
+serial_numbers! { InteractionType;
+	Ping, 1;
+	ApplicationCommand, 2;
+	MessageComponent, 3;
+	ApplicationCommandAutocomplete, 4;
+	ModalSubmit, 5;
+}
+
+/// The type of a message component
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum ComponentType {
+	ActionRow,
+	Button,
+	StringSelect,
+	TextInput,
+	UserSelect,
+	RoleSelect,
+	MentionableSelect,
+	ChannelSelect,
+}
+
+serial_use_mapping!(ComponentType, numeric);
+serial_numbers! { ComponentType;
+	ActionRow, 1;
+	Button, 2;
+	StringSelect, 3;
+	TextInput, 4;
+	UserSelect, 5;
+	RoleSelect, 6;
+	MentionableSelect, 7;
+	ChannelSelect, 8;
+}
+
+/// An interactive element attached to a message or modal
+#[derive(Debug, Clone)]
+pub enum Component {
+	/// A container for other components, laid out in a row
+	ActionRow(ActionRow),
+	Button(Button),
+	/// Any of the kinds of select menu
+	SelectMenu(SelectMenu),
+	/// A text field in a modal
+	TextInput(TextInput),
+	/// A component type not covered by the above
+	Unknown(Value),
+}
+serial_use_mapping!(Component, component);
+serial_decode!(Component);
+
+impl Component {
+	/// Get the type of this component, or `None` if it is unknown.
+	pub fn kind(&self) -> Option<ComponentType> {
+		match *self {
+			Component::ActionRow(_) => Some(ComponentType::ActionRow),
+			Component::Button(_) => Some(ComponentType::Button),
+			Component::SelectMenu(ref menu) => Some(menu.kind),
+			Component::TextInput(_) => Some(ComponentType::TextInput),
+			Component::Unknown(_) => None,
+		}
+	}
+}
+
+/// A row of up to 5 buttons, or a single select menu or text input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionRow {
+	pub components: Vec<Component>,
+}
+
+/// The appearance of a button
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum ButtonStyle {
+	/// Blurple
+	Primary,
+	/// Grey
+	Secondary,
+	/// Green
+	Success,
+	/// Red
+	Danger,
+	/// Grey, navigating to a URL instead of sending an interaction
+	Link,
+}
+
+serial_use_mapping!(ButtonStyle, numeric);
+serial_numbers! { ButtonStyle;
+	Primary, 1;
+	Secondary, 2;
+	Success, 3;
+	Danger, 4;
+	Link, 5;
+}
+
+/// A clickable button
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Button {
+	pub style: ButtonStyle,
+	pub label: Option<String>,
+	pub emoji: Option<ReactionEmoji>,
+	/// Identifier sent with interactions, absent for link buttons
+	pub custom_id: Option<String>,
+	/// The URL of a link button
+	pub url: Option<String>,
+	#[serde(default)]
+	pub disabled: bool,
+}
+
+/// A dropdown menu of text options, users, roles, or channels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectMenu {
+	#[serde(rename = "type")]
+	pub kind: ComponentType,
+	pub custom_id: String,
+	/// The choices of a `StringSelect` menu
+	#[serde(default)]
+	pub options: Vec<SelectOption>,
+	/// The channel types offered by a `ChannelSelect` menu
+	#[serde(default)]
+	pub channel_types: Vec<ChannelType>,
+	pub placeholder: Option<String>,
+	pub min_values: Option<u64>,
+	pub max_values: Option<u64>,
+	#[serde(default)]
+	pub disabled: bool,
+}
+
+/// A choice in a `StringSelect` menu
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelectOption {
+	pub label: String,
+	/// The value sent with interactions when chosen
+	pub value: String,
+	pub description: Option<String>,
+	pub emoji: Option<ReactionEmoji>,
+	#[serde(default)]
+	pub default: bool,
+}
+
+/// A text field in a modal
+///
+/// When received in a `ModalSubmitData`, only `custom_id` and `value` are
+/// present.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextInput {
+	pub custom_id: String,
+	pub style: Option<TextInputStyle>,
+	pub label: Option<String>,
+	/// The text entered or pre-filled
+	pub value: Option<String>,
+	pub placeholder: Option<String>,
+	pub min_length: Option<u64>,
+	pub max_length: Option<u64>,
+	pub required: Option<bool>,
+}
+
+/// A user-triggered interaction with an application
+/// https://discord.com/developers/docs/interactions/receiving-and-responding#interaction-object
+#[derive(Debug, Clone)]
+pub struct Interaction {
+	pub id: InteractionId,
+	pub application_id: ApplicationId,
+	pub kind: InteractionType,
+	/// The command, component or modal data. Absent for pings.
+	pub data: Option<InteractionData>,
+	pub server_id: Option<ServerId>,
+	pub channel_id: Option<ChannelId>,
+	/// The invoking member, when used in a server
+	pub member: Option<Member>,
+	/// The invoking user, when used in a private channel
+	pub user: Option<User>,
+	/// Token for responding to the interaction, valid for 15 minutes
+	pub token: String,
+	pub version: u64,
+	/// The message a component was attached to
+	pub message: Option<Message>,
+	/// The permissions the application has in the channel, as a string
+	pub app_permissions: Option<String>,
+	/// The selected language of the invoking user
+	pub locale: Option<String>,
+	/// The preferred language of the server
+	pub server_locale: Option<String>,
+}
+
+impl Interaction {
+	pub fn decode(value: Value) -> Result<Interaction> {
+		let mut value = try!(into_map(value));
+		let kind = try!(remove(&mut value, "type").and_then(serde));
+		let data = try!(opt(&mut value, "data", |v| match kind {
+			InteractionType::ApplicationCommand | InteractionType::ApplicationCommandAutocomplete => {
+				serde(v).map(InteractionData::ApplicationCommand)
+			}
+			InteractionType::MessageComponent => serde(v).map(InteractionData::MessageComponent),
+			InteractionType::ModalSubmit => serde(v).map(InteractionData::ModalSubmit),
+			InteractionType::Ping => Ok(InteractionData::Unknown(v)),
+		}));
+		warn_json!(
+			value,
+			Interaction {
+				id: try!(remove(&mut value, "id").and_then(InteractionId::decode)),
+				application_id: try!(remove(&mut value, "application_id").and_then(ApplicationId::decode)),
+				kind: kind,
+				data: data,
+				server_id: try!(opt(&mut value, "guild_id", ServerId::decode)),
+				channel_id: try!(opt(&mut value, "channel_id", ChannelId::decode)),
+				member: try!(opt(&mut value, "member", Member::decode)),
+				user: try!(opt(&mut value, "user", User::decode)),
+				token: try!(remove(&mut value, "token").and_then(into_string)),
+				version: req!(try!(remove(&mut value, "version")).as_u64()),
+				message: try!(opt(&mut value, "message", Message::decode)),
+				app_permissions: try!(opt(&mut value, "app_permissions", into_string)),
+				locale: try!(opt(&mut value, "locale", into_string)),
+				server_locale: try!(opt(&mut value, "guild_locale", into_string)),
+			}
+		)
+	}
+
+	/// Get the user who triggered the interaction, whether in a server or a
+	/// private channel.
+	pub fn author(&self) -> Option<&User> {
+		match self.member {
+			Some(ref member) => Some(&member.user),
+			None => self.user.as_ref(),
+		}
+	}
+}
+
+/// The data carried by an `Interaction`, depending on its type
+#[derive(Debug, Clone)]
+pub enum InteractionData {
+	/// Sent for application commands and autocomplete
+	ApplicationCommand(CommandData),
+	MessageComponent(ComponentData),
+	ModalSubmit(ModalSubmitData),
+	/// Data for an interaction type not covered by the above
+	Unknown(Value),
+}
+
+/// The application command used in an `Interaction`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandData {
+	pub id: CommandId,
+	pub name: String,
+	#[serde(rename = "type")]
+	#[serde(default)]
+	pub kind: ApplicationCommandType,
+	/// Users, roles, channels and other objects referenced by the options
+	#[serde(default)]
+	pub resolved: ResolvedData,
+	/// The options filled in, or the subcommand used
+	#[serde(default)]
+	pub options: Vec<CommandDataOption>,
+	/// The server the command is registered in, for server commands
+	#[serde(rename = "guild_id")]
+	pub server_id: Option<ServerId>,
+	/// The user or message targeted by a context menu command
+	#[serde(default)]
+	#[serde(deserialize_with = "::serial::deserialize_id_opt")]
+	pub target_id: Option<u64>,
+}
+serial_decode!(CommandData);
+
+/// An option filled in by the user when using an application command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandDataOption {
+	pub name: String,
+	#[serde(rename = "type")]
+	pub kind: ApplicationCommandOptionType,
+	/// The value of the option, absent for subcommands and groups
+	pub value: Option<Value>,
+	/// The options of a subcommand, or the subcommand of a group
+	#[serde(default)]
+	pub options: Vec<CommandDataOption>,
+	/// Whether this option is being autocompleted
+	#[serde(default)]
+	pub focused: bool,
+}
+serial_decode!(CommandDataOption);
+
+/// Objects referenced by the options or target of an application command
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResolvedData {
+	#[serde(default)]
+	pub users: BTreeMap<UserId, User>,
+	/// Partial members, missing the `user`, `deaf` and `mute` fields
+	#[serde(default)]
+	pub members: BTreeMap<UserId, Value>,
+	#[serde(default)]
+	pub roles: BTreeMap<RoleId, Role>,
+	/// Partial channels, with an ID, name, type and permissions
+	#[serde(default)]
+	pub channels: BTreeMap<ChannelId, Value>,
+	#[serde(default)]
+	pub messages: BTreeMap<MessageId, Message>,
+	#[serde(default)]
+	pub attachments: BTreeMap<String, Attachment>,
+}
+
+/// The message component used in an `Interaction`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentData {
+	pub custom_id: String,
+	pub component_type: ComponentType,
+	/// The options chosen in a select menu
+	#[serde(default)]
+	pub values: Vec<String>,
+}
+serial_decode!(ComponentData);
+
+/// The modal submitted in an `Interaction`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModalSubmitData {
+	pub custom_id: String,
+	/// The action rows of the modal, containing the values of its text inputs
+	pub components: Vec<Component>,
+}
+serial_decode!(ModalSubmitData);
+
+/// The style of a text input in a modal
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum TextInputStyle {
+	/// A single-line input
+	Short,
+	/// A multi-line input
+	Paragraph,
+}
+
+serial_use_mapping!(TextInputStyle, numeric);
+serial_numbers! { TextInputStyle;
+	Short, 1;
+	Paragraph, 2;
+}
+
+/// The type of a response to an interaction
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub enum InteractionResponseType {
+	/// Acknowledge a ping
+	Pong,
+	/// Respond with a message
+	ChannelMessageWithSource,
+	/// Acknowledge the interaction, showing a loading state, and send a
+	/// message later by editing the original response
+	DeferredChannelMessageWithSource,
+	/// Acknowledge a component interaction, and edit its message later
+	DeferredUpdateMessage,
+	/// Edit the message a component was attached to
+	UpdateMessage,
+	/// Respond to an autocomplete interaction with suggested choices
+	ApplicationCommandAutocompleteResult,
+	/// Respond with a popup modal
+	Modal,
+}
+
+serial_use_mapping!(InteractionResponseType, numeric);
+serial_numbers! { InteractionResponseType;
+	Pong, 1;
+	ChannelMessageWithSource, 4;
+	DeferredChannelMessageWithSource, 5;
+	DeferredUpdateMessage, 6;
+	UpdateMessage, 7;
+	ApplicationCommandAutocompleteResult, 8;
+	Modal, 9;
+}
+
 //=================
 // Event model
 
@@ -1243,6 +2416,8 @@ pub struct VoiceState {
 	pub self_deaf: bool,
 	pub mute: bool,
 	pub deaf: bool,
+	/// When the user asked to speak in a stage channel, if they are waiting
+	pub request_to_speak_timestamp: Option<DateTime<FixedOffset>>,
 }
 serial_decode!(VoiceState);
 
@@ -1282,6 +2457,107 @@ pub struct Emoji {
 }
 serial_decode!(Emoji);
 
+impl Emoji {
+	/// Returns the formatted URL of the emoji's image.
+	pub fn url(&self) -> String {
+		self.image().into()
+	}
+
+	/// Returns the emoji's image, which can be resized or converted.
+	pub fn image(&self) -> ImageUrl {
+		cdn::emoji(self.id, self.animated)
+	}
+}
+
+/// A sticker which can be sent in messages
+/// https://discord.com/developers/docs/resources/sticker#sticker-object
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sticker {
+	pub id: StickerId,
+	/// The pack of a standard sticker
+	#[serde(default)]
+	#[serde(deserialize_with = "::serial::deserialize_id_opt")]
+	pub pack_id: Option<u64>,
+	pub name: String,
+	pub description: Option<String>,
+	/// Comma-separated autocomplete keywords; for server stickers, the name
+	/// of a related unicode emoji
+	pub tags: String,
+	#[serde(rename = "type")]
+	pub kind: StickerType,
+	pub format_type: StickerFormatType,
+	/// Whether a server sticker can be used, which may be false if the
+	/// server has lost boosts
+	pub available: Option<bool>,
+	#[serde(rename = "guild_id")]
+	pub server_id: Option<ServerId>,
+	/// The user who uploaded a server sticker
+	pub user: Option<User>,
+	pub sort_value: Option<u64>,
+}
+serial_decode!(Sticker);
+
+impl Sticker {
+	/// Returns the formatted URL of the sticker's file.
+	pub fn url(&self) -> String {
+		cdn::sticker(self.id, self.format_type)
+	}
+}
+
+/// The minimal information about a sticker sent in a message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StickerItem {
+	pub id: StickerId,
+	pub name: String,
+	pub format_type: StickerFormatType,
+}
+serial_decode!(StickerItem);
+
+impl StickerItem {
+	/// Returns the formatted URL of the sticker's file.
+	pub fn url(&self) -> String {
+		cdn::sticker(self.id, self.format_type)
+	}
+}
+
+/// Whether a `Sticker` is a standard or server sticker
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum StickerType {
+	/// An official sticker in a pack
+	Standard,
+	/// A sticker uploaded to a server
+	Server,
+	/// A sticker type not covered by the above
+	Unknown(u64),
+}
+
+serial_use_mapping!(StickerType, numeric);
+serial_numbers! { StickerType(Unknown);
+	Standard, 1;
+	Server, 2;
+}
+
+/// The image format of a sticker
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum StickerFormatType {
+	Png,
+	/// Animated PNG
+	Apng,
+	/// A Lottie animation, as JSON
+	Lottie,
+	Gif,
+	/// A format not covered by the above
+	Unknown(u64),
+}
+
+serial_use_mapping!(StickerFormatType, numeric);
+serial_numbers! { StickerFormatType(Unknown);
+	Png, 1;
+	Apng, 2;
+	Lottie, 3;
+	Gif, 4;
+}
+
 /// A full single reaction
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Reaction {
@@ -1328,6 +2604,12 @@ pub struct LiveServer {
 	pub large: bool,
 	pub channels: Vec<PublicChannel>,
 	pub categories: Vec<ChannelCategory>,
+	/// Active threads which the current user can see
+	pub threads: Vec<PublicChannel>,
+	pub scheduled_events: Vec<ScheduledEvent>,
+	/// Stages which are live in the server's stage channels
+	pub stage_instances: Vec<StageInstance>,
+	pub stickers: Vec<Sticker>,
 	pub afk_timeout: u64,
 	pub afk_channel_id: Option<ChannelId>,
 	pub system_channel_id: Option<ChannelId>,
@@ -1383,6 +2665,18 @@ impl LiveServer {
 					try!(get(&mut value, "channels")),
 					ChannelCategory::decode
 				)),
+				threads: try!(opt(&mut value, "threads", |v| decode_array(v, |v| {
+					PublicChannel::decode_server(v, id)
+				})))
+				.unwrap_or_default(),
+				scheduled_events: try!(opt(&mut value, "guild_scheduled_events", |v| decode_array(
+					v,
+					ScheduledEvent::decode
+				)))
+				.unwrap_or_default(),
+				stage_instances: try!(opt(&mut value, "stage_instances", |v| decode_array(v, StageInstance::decode)))
+					.unwrap_or_default(),
+				stickers: try!(opt(&mut value, "stickers", |v| decode_array(v, Sticker::decode))).unwrap_or_default(),
 				verification_level: try!(remove(&mut value, "verification_level").and_then(serde)),
 				emojis: try!(
 					remove(&mut value, "emojis").and_then(|v| decode_array(v, Emoji::decode))
@@ -1408,9 +2702,19 @@ impl LiveServer {
 	///
 	/// Returns None if the server does not have an icon.
 	pub fn icon_url(&self) -> Option<String> {
-		self.icon
+		self.icon_image().map(String::from)
+	}
+
+	/// Returns the server's icon, which can be resized or converted.
+	pub fn icon_image(&self) -> Option<ImageUrl> {
+		self.icon.as_ref().map(|icon| cdn::server_icon(self.id, icon))
+	}
+
+	/// Returns the formatted URL of the server's invite splash image.
+	pub fn splash_url(&self) -> Option<String> {
+		self.splash
 			.as_ref()
-			.map(|icon| format!(cdn_concat!("/icons/{}/{}.jpg"), self.id, icon))
+			.map(|splash| cdn::server_splash(self.id, splash).into())
 	}
 
 	/// Calculate the effective permissions for a specific user in a specific
@@ -1588,6 +2892,15 @@ pub struct CurrentUserPatch {
 serial_decode!(CurrentUserPatch);
 
 impl CurrentUser {
+	/// Returns the formatted URL of the user's avatar, or of their default
+	/// avatar if they have not set one.
+	pub fn face(&self) -> String {
+		match self.avatar {
+			Some(ref av) => cdn::user_avatar(self.id, av).into(),
+			None => cdn::default_avatar(self.id, self.discriminator).into(),
+		}
+	}
+
 	pub fn update_from(&mut self, patch: &CurrentUserPatch) {
 		update_field(&mut self.id, &patch.id);
 		update_field(&mut self.username, &patch.username);
@@ -1626,6 +2939,154 @@ mod test {
 		}
 	}
 
+	#[test]
+	fn test_interaction_create() {
+		let event = Event::decode(
+			"INTERACTION_CREATE".into(),
+			::serde_json::from_str(r#"{
+				"id": "8", "application_id": "1", "type": 2, "token": "tok", "version": 1,
+				"guild_id": "7", "channel_id": "3",
+				"member": {"user": {"id": "4", "username": "user", "discriminator": "0001", "avatar": null},
+					"roles": [], "nick": null, "joined_at": "2021-01-01T00:00:00+00:00", "mute": false, "deaf": false},
+				"data": {"id": "10", "name": "roll", "type": 1,
+					"options": [{"name": "sides", "type": 4, "value": 6}],
+					"resolved": {"users": {"4": {"id": "4", "username": "user", "discriminator": "0001", "avatar": null}}}}
+			}"#)
+			.unwrap(),
+		)
+		.unwrap();
+		let interaction = match event {
+			Event::InteractionCreate(interaction) => interaction,
+			other => panic!("unexpected event {:?}", other),
+		};
+		assert_eq!(interaction.author().map(|u| u.id), Some(UserId(4)));
+		match interaction.data {
+			Some(InteractionData::ApplicationCommand(data)) => {
+				assert_eq!(data.name, "roll");
+				assert_eq!(data.options[0].value, Some(6.into()));
+				assert!(data.resolved.users.contains_key(&UserId(4)));
+			}
+			other => panic!("unexpected data {:?}", other),
+		}
+	}
+
+	#[test]
+	fn test_components() {
+		let row: Component = ::serde_json::from_str(r#"{"type": 1, "components": [
+			{"type": 2, "style": 1, "label": "Yes", "custom_id": "yes", "emoji": {"name": "👍", "id": null}},
+			{"type": 5, "custom_id": "who", "max_values": 2},
+			{"type": 99}
+		]}"#)
+		.unwrap();
+		let components = match row {
+			Component::ActionRow(ref row) => &row.components,
+			ref other => panic!("unexpected component {:?}", other),
+		};
+		match components[0] {
+			Component::Button(ref button) => {
+				assert_eq!(button.style, ButtonStyle::Primary);
+				assert_eq!(button.emoji, Some(ReactionEmoji::Unicode("👍".into())));
+			}
+			ref other => panic!("unexpected component {:?}", other),
+		}
+		assert_eq!(components[1].kind(), Some(ComponentType::UserSelect));
+		assert_eq!(components[2].kind(), None);
+
+		let value = ::serde_json::to_value(&row).unwrap();
+		assert_eq!(value["type"], 1);
+		assert_eq!(value["components"][0]["type"], 2);
+		assert_eq!(value["components"][1]["type"], 5);
+		assert_eq!(value["components"][2]["type"], 99);
+	}
+
+	#[test]
+	fn test_thread_events() {
+		const THREAD: &'static str = r#"{"id": "20", "guild_id": "7", "parent_id": "3", "owner_id": "4",
+			"name": "topic", "type": 11, "last_message_id": null, "message_count": 1, "member_count": 2,
+			"thread_metadata": {"archived": false, "auto_archive_duration": 60,
+				"archive_timestamp": "2021-01-01T00:00:00+00:00", "locked": false},
+			"member": {"join_timestamp": "2021-01-01T00:00:00+00:00", "flags": 0}}"#;
+		match Event::decode("THREAD_CREATE".into(), ::serde_json::from_str(THREAD).unwrap()).unwrap() {
+			Event::ThreadCreate(thread) => {
+				assert!(thread.is_thread());
+				assert_eq!(thread.parent_id, Some(ChannelId(3)));
+				assert_eq!(thread.thread_metadata.map(|m| m.auto_archive_duration), Some(60));
+				assert!(thread.member.is_some());
+				assert!(thread.permission_overwrites.is_empty());
+			}
+			other => panic!("unexpected event {:?}", other),
+		}
+
+		let sync = format!(
+			r#"{{"guild_id": "7", "channel_ids": ["3"], "threads": [{}],
+				"members": [{{"id": "20", "user_id": "1", "join_timestamp": "2021-01-01T00:00:00+00:00", "flags": 0}}]}}"#,
+			THREAD
+		);
+		match Event::decode("THREAD_LIST_SYNC".into(), ::serde_json::from_str(&sync).unwrap()).unwrap() {
+			Event::ThreadListSync { channel_ids, threads, members, .. } => {
+				assert_eq!(channel_ids, Some(vec![ChannelId(3)]));
+				assert_eq!(threads[0].id, ChannelId(20));
+				assert_eq!(members[0].thread_id, Some(ChannelId(20)));
+			}
+			other => panic!("unexpected event {:?}", other),
+		}
+	}
+
+	#[test]
+	fn scheduled_events() {
+		let event = r#"{"id":"5","guild_id":"1","channel_id":null,"creator_id":"2","name":"Meetup",
+			"description":null,"scheduled_start_time":"2022-03-01T18:00:00+00:00",
+			"scheduled_end_time":"2022-03-01T20:00:00+00:00","privacy_level":2,"status":1,
+			"entity_type":3,"entity_id":null,"entity_metadata":{"location":"Town hall"},"image":null}"#;
+		match Event::decode("GUILD_SCHEDULED_EVENT_CREATE".into(), ::serde_json::from_str(event).unwrap()).unwrap() {
+			Event::ServerScheduledEventCreate(event) => {
+				assert_eq!(event.server_id, ServerId(1));
+				assert_eq!(event.entity_type, ScheduledEventEntityType::External);
+				assert_eq!(event.status, ScheduledEventStatus::Scheduled);
+				assert_eq!(event.entity_metadata.unwrap().location.unwrap(), "Town hall");
+			}
+			other => panic!("unexpected event {:?}", other),
+		}
+		let add = r#"{"guild_scheduled_event_id":"5","user_id":"3","guild_id":"1"}"#;
+		match Event::decode("GUILD_SCHEDULED_EVENT_USER_ADD".into(), ::serde_json::from_str(add).unwrap()).unwrap() {
+			Event::ServerScheduledEventUserAdd { event_id, user_id, .. } => {
+				assert_eq!((event_id, user_id), (ScheduledEventId(5), UserId(3)));
+			}
+			other => panic!("unexpected event {:?}", other),
+		}
+	}
+
+	#[test]
+	fn stage_channels() {
+		let channel = r#"{"id":"7","guild_id":"1","name":"Talks","type":13,"position":0,"bitrate":64000,"user_limit":0}"#;
+		match Channel::decode(::serde_json::from_str(channel).unwrap()).unwrap() {
+			Channel::Public(channel) => assert_eq!(channel.kind, ChannelType::StageVoice),
+			other => panic!("unexpected channel {:?}", other),
+		}
+		let stage = r#"{"id":"8","guild_id":"1","channel_id":"7","topic":"Weekly talk","privacy_level":2,
+			"discoverable_disabled":false,"guild_scheduled_event_id":"5"}"#;
+		match Event::decode("STAGE_INSTANCE_CREATE".into(), ::serde_json::from_str(stage).unwrap()).unwrap() {
+			Event::StageInstanceCreate(stage) => {
+				assert_eq!(stage.channel_id, ChannelId(7));
+				assert_eq!(stage.privacy_level, StagePrivacyLevel::ServerOnly);
+				assert_eq!(stage.scheduled_event_id, Some(ScheduledEventId(5)));
+			}
+			other => panic!("unexpected event {:?}", other),
+		}
+	}
+
+	#[test]
+	fn integration() {
+		let integration = r#"{"id":"6","name":"streamer","type":"twitch","enabled":true,"syncing":false,
+			"role_id":"4","enable_emoticons":true,"expire_behavior":1,"expire_grace_period":7,
+			"account":{"id":"abc","name":"streamer"},"synced_at":"2022-01-01T00:00:00+00:00",
+			"subscriber_count":12,"revoked":false}"#;
+		let integration = Integration::decode(::serde_json::from_str(integration).unwrap()).unwrap();
+		assert_eq!(integration.role_id, Some(RoleId(4)));
+		assert_eq!(integration.expire_behavior, Some(IntegrationExpireBehavior::Kick));
+		assert_eq!(integration.account.id, "abc");
+	}
+
 	#[test]
 	fn test_update_user() {
 		let u1 = CurrentUser {
@@ -1718,6 +3179,22 @@ pub struct ApplicationInfo {
 }
 serial_decode!(ApplicationInfo);
 
+impl ApplicationInfo {
+	/// Returns the formatted URL of the application's icon.
+	///
+	/// Returns None if the application does not have an icon.
+	pub fn icon_url(&self) -> Option<String> {
+		self.icon_image().map(String::from)
+	}
+
+	/// Returns the application's icon, which can be resized or converted.
+	pub fn icon_image(&self) -> Option<ImageUrl> {
+		self.icon
+			.as_ref()
+			.map(|icon| cdn::application_icon(ApplicationId(self.id.0), icon))
+	}
+}
+
 /// A type of relationship this user has with another.
 #[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
 pub enum RelationshipType {
@@ -2120,8 +3597,33 @@ pub enum Event {
 	ServerBanAdd(ServerId, User),
 	ServerBanRemove(ServerId, User),
 
+	/// A server's integrations changed; fetch them with
+	/// `Discord::get_server_integrations`
 	ServerIntegrationsUpdate(ServerId),
 	ServerEmojisUpdate(ServerId, Vec<Emoji>),
+	ServerStickersUpdate(ServerId, Vec<Sticker>),
+
+	ServerScheduledEventCreate(ScheduledEvent),
+	ServerScheduledEventUpdate(ScheduledEvent),
+	ServerScheduledEventDelete(ScheduledEvent),
+	/// A user subscribed to a scheduled event
+	ServerScheduledEventUserAdd {
+		event_id: ScheduledEventId,
+		user_id: UserId,
+		server_id: ServerId,
+	},
+	/// A user unsubscribed from a scheduled event
+	ServerScheduledEventUserRemove {
+		event_id: ScheduledEventId,
+		user_id: UserId,
+		server_id: ServerId,
+	},
+
+	/// A stage was started in a stage channel
+	StageInstanceCreate(StageInstance),
+	StageInstanceUpdate(StageInstance),
+	/// A stage was ended
+	StageInstanceDelete(StageInstance),
 
 	ChannelCreate(Channel),
 	ChannelUpdate(Channel),
@@ -2138,6 +3640,38 @@ pub enum Event {
 	ReactionAdd(Reaction),
 	ReactionRemove(Reaction),
 
+	/// A thread was created, or the current user was added to a private thread
+	ThreadCreate(PublicChannel),
+	ThreadUpdate(PublicChannel),
+	ThreadDelete {
+		id: ChannelId,
+		server_id: ServerId,
+		parent_id: Option<ChannelId>,
+		kind: ChannelType,
+	},
+	/// Sent when gaining access to a channel, with its active threads
+	ThreadListSync {
+		server_id: ServerId,
+		/// The parent channels being synced, or `None` for the whole server
+		channel_ids: Option<Vec<ChannelId>>,
+		threads: Vec<PublicChannel>,
+		/// The current user's memberships of the threads
+		members: Vec<ThreadMember>,
+	},
+	/// The current user's membership of a thread was updated
+	ThreadMemberUpdate(ServerId, ThreadMember),
+	/// Users were added to or removed from a thread
+	ThreadMembersUpdate {
+		id: ChannelId,
+		server_id: ServerId,
+		member_count: u64,
+		added_members: Vec<ThreadMember>,
+		removed_member_ids: Vec<UserId>,
+	},
+
+	/// A user used an application command, component or modal
+	InteractionCreate(Interaction),
+
 	/// An event type not covered by the above
 	Unknown(String, Object),
 	// Any other event. Should never be used directly.
@@ -2485,6 +4019,44 @@ impl Event {
 					try!(remove(&mut value, "emojis").and_then(|v| decode_array(v, Emoji::decode))),
 				)
 			)
+		} else if kind == "GUILD_STICKERS_UPDATE" {
+			warn_json!(
+				value,
+				Event::ServerStickersUpdate(
+					try!(remove(&mut value, "guild_id").and_then(ServerId::decode)),
+					try!(remove(&mut value, "stickers").and_then(|v| decode_array(v, Sticker::decode))),
+				)
+			)
+		} else if kind == "GUILD_SCHEDULED_EVENT_CREATE" {
+			ScheduledEvent::decode(Value::Object(value)).map(Event::ServerScheduledEventCreate)
+		} else if kind == "GUILD_SCHEDULED_EVENT_UPDATE" {
+			ScheduledEvent::decode(Value::Object(value)).map(Event::ServerScheduledEventUpdate)
+		} else if kind == "GUILD_SCHEDULED_EVENT_DELETE" {
+			ScheduledEvent::decode(Value::Object(value)).map(Event::ServerScheduledEventDelete)
+		} else if kind == "GUILD_SCHEDULED_EVENT_USER_ADD" {
+			warn_json!(
+				value,
+				Event::ServerScheduledEventUserAdd {
+					event_id: try!(remove(&mut value, "guild_scheduled_event_id").and_then(ScheduledEventId::decode)),
+					user_id: try!(remove(&mut value, "user_id").and_then(UserId::decode)),
+					server_id: try!(remove(&mut value, "guild_id").and_then(ServerId::decode)),
+				}
+			)
+		} else if kind == "GUILD_SCHEDULED_EVENT_USER_REMOVE" {
+			warn_json!(
+				value,
+				Event::ServerScheduledEventUserRemove {
+					event_id: try!(remove(&mut value, "guild_scheduled_event_id").and_then(ScheduledEventId::decode)),
+					user_id: try!(remove(&mut value, "user_id").and_then(UserId::decode)),
+					server_id: try!(remove(&mut value, "guild_id").and_then(ServerId::decode)),
+				}
+			)
+		} else if kind == "STAGE_INSTANCE_CREATE" {
+			StageInstance::decode(Value::Object(value)).map(Event::StageInstanceCreate)
+		} else if kind == "STAGE_INSTANCE_UPDATE" {
+			StageInstance::decode(Value::Object(value)).map(Event::StageInstanceUpdate)
+		} else if kind == "STAGE_INSTANCE_DELETE" {
+			StageInstance::decode(Value::Object(value)).map(Event::StageInstanceDelete)
 		} else if kind == "CHANNEL_CREATE" {
 			Channel::decode(Value::Object(value)).map(Event::ChannelCreate)
 		} else if kind == "CHANNEL_UPDATE" {
@@ -2507,6 +4079,48 @@ impl Event {
 					last_pin_timestamp: try!(opt(&mut value, "last_pin_timestamp", into_timestamp)),
 				}
 			)
+		} else if kind == "THREAD_CREATE" {
+			PublicChannel::decode(Value::Object(value)).map(Event::ThreadCreate)
+		} else if kind == "THREAD_UPDATE" {
+			PublicChannel::decode(Value::Object(value)).map(Event::ThreadUpdate)
+		} else if kind == "THREAD_DELETE" {
+			warn_json!(
+				value,
+				Event::ThreadDelete {
+					id: try!(remove(&mut value, "id").and_then(ChannelId::decode)),
+					server_id: try!(remove(&mut value, "guild_id").and_then(ServerId::decode)),
+					parent_id: try!(opt(&mut value, "parent_id", ChannelId::decode)),
+					kind: try!(remove(&mut value, "type").and_then(serde)),
+				}
+			)
+		} else if kind == "THREAD_LIST_SYNC" {
+			warn_json!(
+				value,
+				Event::ThreadListSync {
+					server_id: try!(remove(&mut value, "guild_id").and_then(ServerId::decode)),
+					channel_ids: try!(opt(&mut value, "channel_ids", |v| decode_array(v, ChannelId::decode))),
+					threads: try!(remove(&mut value, "threads").and_then(|v| decode_array(v, PublicChannel::decode))),
+					members: try!(remove(&mut value, "members").and_then(|v| decode_array(v, ThreadMember::decode))),
+				}
+			)
+		} else if kind == "THREAD_MEMBER_UPDATE" {
+			let server_id = try!(remove(&mut value, "guild_id").and_then(ServerId::decode));
+			ThreadMember::decode(Value::Object(value)).map(|member| Event::ThreadMemberUpdate(server_id, member))
+		} else if kind == "THREAD_MEMBERS_UPDATE" {
+			warn_json!(
+				value,
+				Event::ThreadMembersUpdate {
+					id: try!(remove(&mut value, "id").and_then(ChannelId::decode)),
+					server_id: try!(remove(&mut value, "guild_id").and_then(ServerId::decode)),
+					member_count: req!(try!(remove(&mut value, "member_count")).as_u64()),
+					added_members: try!(opt(&mut value, "added_members", |v| decode_array(v, ThreadMember::decode)))
+						.unwrap_or_default(),
+					removed_member_ids: try!(opt(&mut value, "removed_member_ids", |v| decode_array(v, UserId::decode)))
+						.unwrap_or_default(),
+				}
+			)
+		} else if kind == "INTERACTION_CREATE" {
+			Interaction::decode(Value::Object(value)).map(Event::InteractionCreate)
 		} else {
 			Ok(Event::Unknown(kind, value))
 		}
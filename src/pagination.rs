@@ -0,0 +1,227 @@
+//! Iterators which fetch paginated REST resources on demand.
+
+use std::collections::VecDeque;
+
+use model::*;
+use {Discord, GetMessages, Result};
+
+/// The most messages Discord returns in one page.
+const MESSAGE_PAGE: u64 = 100;
+/// The most members Discord returns in one page.
+pub const MEMBER_PAGE: u32 = 1000;
+/// The most reacting users Discord returns in one page.
+pub const REACTION_PAGE: u32 = 100;
+/// The most bans Discord returns in one page.
+pub const BAN_PAGE: u32 = 1000;
+/// The most servers Discord returns in one page.
+pub const SERVER_PAGE: u32 = 200;
+/// The most scheduled event subscribers Discord returns in one page.
+pub const EVENT_USER_PAGE: u32 = 100;
+
+/// An iterator over a channel's messages, fetching pages of 100 as needed.
+///
+/// Created by `Discord::message_history`. By default, messages are yielded
+/// from the most recent backwards; use `before` to start from a given
+/// message, or `after` to walk forwards from a given message towards the
+/// present. Each page is requested through the usual rate limiting.
+///
+/// An error ends the iteration after being yielded.
+///
+/// ```ignore
+/// // Every message from the last day, newest first
+/// let cutoff = Utc::now() - Duration::days(1);
+/// for message in discord.message_history(channel).take_while(|m| match *m {
+///     Ok(ref m) => m.timestamp > cutoff,
+///     Err(_) => true,
+/// }) {
+///     archive(message?);
+/// }
+/// ```
+pub struct MessageHistory<'a> {
+	discord: &'a Discord,
+	channel: ChannelId,
+	cursor: GetMessages,
+	buffer: VecDeque<Message>,
+	done: bool,
+}
+
+impl<'a> MessageHistory<'a> {
+	#[doc(hidden)]
+	pub fn __new(discord: &'a Discord, channel: ChannelId) -> MessageHistory<'a> {
+		MessageHistory {
+			discord: discord,
+			channel: channel,
+			cursor: GetMessages::MostRecent,
+			buffer: VecDeque::new(),
+			done: false,
+		}
+	}
+
+	/// Yield messages older than the given message, newest first.
+	pub fn before(mut self, message: MessageId) -> Self {
+		self.cursor = GetMessages::Before(message);
+		self
+	}
+
+	/// Yield messages newer than the given message, oldest first.
+	///
+	/// Pass `MessageId(0)` to start from the beginning of the channel.
+	pub fn after(mut self, message: MessageId) -> Self {
+		self.cursor = GetMessages::After(message);
+		self
+	}
+
+	fn fetch(&mut self) -> Result<()> {
+		let cursor = self.cursor;
+		let forwards = match cursor {
+			GetMessages::After(_) => true,
+			_ => false,
+		};
+		// Pages are always sorted newest first
+		let mut page = self
+			.discord
+			.get_messages(self.channel, cursor, Some(MESSAGE_PAGE))?;
+		if (page.len() as u64) < MESSAGE_PAGE {
+			self.done = true;
+		}
+		if forwards {
+			page.reverse();
+		}
+		if let Some(last) = page.last() {
+			self.cursor = if forwards {
+				GetMessages::After(last.id)
+			} else {
+				GetMessages::Before(last.id)
+			};
+		} else {
+			self.done = true;
+		}
+		self.buffer.extend(page);
+		Ok(())
+	}
+}
+
+impl<'a> Iterator for MessageHistory<'a> {
+	type Item = Result<Message>;
+
+	fn next(&mut self) -> Option<Result<Message>> {
+		if self.buffer.is_empty() && !self.done {
+			if let Err(err) = self.fetch() {
+				self.done = true;
+				return Some(Err(err));
+			}
+		}
+		self.buffer.pop_front().map(Ok)
+	}
+}
+
+/// An iterator over a list which Discord returns in pages ordered by ID,
+/// fetching pages of the largest allowed size as needed.
+///
+/// Created by `Discord::all_server_members`, `all_reactions`, `all_bans`,
+/// `all_servers` and `all_scheduled_event_users`. Each page is requested
+/// through the usual rate limiting, and an error ends the iteration after
+/// being yielded.
+pub struct Paginated<'a, T> {
+	fetch: Box<dyn FnMut(Option<u64>) -> Result<Vec<T>> + 'a>,
+	cursor_of: fn(&T) -> u64,
+	page_size: usize,
+	cursor: Option<u64>,
+	buffer: VecDeque<T>,
+	done: bool,
+}
+
+impl<'a, T> Paginated<'a, T> {
+	#[doc(hidden)]
+	pub fn __new<F>(page_size: usize, cursor_of: fn(&T) -> u64, fetch: F) -> Paginated<'a, T>
+	where
+		F: FnMut(Option<u64>) -> Result<Vec<T>> + 'a,
+	{
+		Paginated {
+			fetch: Box::new(fetch),
+			cursor_of: cursor_of,
+			page_size: page_size,
+			cursor: None,
+			buffer: VecDeque::new(),
+			done: false,
+		}
+	}
+
+	fn fetch(&mut self) -> Result<()> {
+		let page = (self.fetch)(self.cursor)?;
+		if page.len() < self.page_size {
+			self.done = true;
+		}
+		match page.last() {
+			Some(last) => self.cursor = Some((self.cursor_of)(last)),
+			None => self.done = true,
+		}
+		self.buffer.extend(page);
+		Ok(())
+	}
+}
+
+impl<'a, T> Iterator for Paginated<'a, T> {
+	type Item = Result<T>;
+
+	fn next(&mut self) -> Option<Result<T>> {
+		if self.buffer.is_empty() && !self.done {
+			if let Err(err) = self.fetch() {
+				self.done = true;
+				return Some(Err(err));
+			}
+		}
+		self.buffer.pop_front().map(Ok)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use test_support::{mock_discord, mock_responses, MESSAGE};
+
+	fn message_page(ids: ::std::ops::Range<u64>) -> (&'static str, String) {
+		let messages: Vec<String> = ids.rev().map(|id| MESSAGE.replacen("\"2\"", &format!("\"{}\"", id), 1)).collect();
+		("200 OK", format!("[{}]", messages.join(",")))
+	}
+
+	#[test]
+	fn message_history() {
+		// Pages are returned newest first, like Discord does
+		let (base, requests) = mock_responses(vec![message_page(101..201), message_page(201..231)]);
+		let discord = mock_discord(&base);
+		let ids: Vec<u64> = discord
+			.message_history(ChannelId(3))
+			.after(MessageId(100))
+			.map(|m| m.unwrap().id.0)
+			.collect();
+		assert_eq!(ids, (101..231).collect::<Vec<_>>());
+		assert!(requests.recv().unwrap().starts_with("GET /api/channels/3/messages?limit=100&after=100 "));
+		assert!(requests.recv().unwrap().starts_with("GET /api/channels/3/messages?limit=100&after=200 "));
+
+		let (base, _requests) = mock_responses(vec![message_page(1..101)]);
+		let discord = mock_discord(&base);
+		let ids: Vec<u64> = discord
+			.message_history(ChannelId(3))
+			.take_while(|m| m.as_ref().map(|m| m.id.0 > 95).unwrap_or(true))
+			.map(|m| m.unwrap().id.0)
+			.collect();
+		assert_eq!(ids, vec![100, 99, 98, 97, 96]);
+	}
+
+	#[test]
+	fn paginated_bans() {
+		let ban = |id: u64| format!(r#"{{"reason":null,"user":{{"id":"{}","username":"u","discriminator":"0001","avatar":null}}}}"#, id);
+		let first: Vec<String> = (1..1001).map(&ban).collect();
+		let (base, requests) = mock_responses(vec![
+			("200 OK", format!("[{}]", first.join(","))),
+			("200 OK", format!("[{}]", ban(1001))),
+		]);
+		let discord = mock_discord(&base);
+		let bans: Vec<Ban> = discord.all_bans(ServerId(3)).map(|b| b.unwrap()).collect();
+		assert_eq!(bans.len(), 1001);
+		assert_eq!(bans[1000].user.id, UserId(1001));
+		assert!(requests.recv().unwrap().starts_with("GET /api/guilds/3/bans?limit=1000 "));
+		assert!(requests.recv().unwrap().starts_with("GET /api/guilds/3/bans?limit=1000&after=1000 "));
+	}
+}
@@ -1,136 +1,514 @@
 use std;
-use std::collections::BTreeMap;
-use std::sync::Mutex;
+use std::collections::{BTreeMap, BTreeSet};
+#[cfg(feature = "async")]
+use std::future::Future;
+use std::mem;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+use std::task::Waker;
+use std::time::{Duration, Instant};
 
 use chrono::prelude::*;
 use hyper;
+use hyper::header::Headers;
+use hyper::status::StatusCode;
 
 use {Error, Result};
 
-#[derive(Default)]
+/// A source of time which rate limits wait on, replaceable in tests.
+pub trait Clock: Send + Sync {
+	/// The current time.
+	fn now(&self) -> Instant;
+	/// Block the current thread until the given time.
+	fn sleep_until(&self, deadline: Instant);
+}
+
+struct SystemClock;
+
+impl Clock for SystemClock {
+	fn now(&self) -> Instant {
+		Instant::now()
+	}
+
+	fn sleep_until(&self, deadline: Instant) {
+		let now = Instant::now();
+		if deadline > now {
+			std::thread::sleep(deadline - now);
+		}
+	}
+}
+
+/// Rate limit state shared by all requests made by a client.
+///
+/// Discord groups routes into buckets, identified by the `X-RateLimit-Bucket`
+/// header, and limits each bucket separately for every value of the route's
+/// major parameter (its channel, server or webhook). Until a route's bucket
+/// is learned from a response, the route is limited on its own.
+///
+/// Requests for the same bucket wait their turn in the order they arrived,
+/// whether they wait by blocking in `pre_check` or asynchronously in `wait`.
+/// No lock is held while waiting for a bucket to reset, so a throttled bucket
+/// never delays requests to other buckets.
 pub struct RateLimits {
-	global: Mutex<RateLimit>,
-	endpoints: Mutex<BTreeMap<String, RateLimit>>,
+	clock: Arc<dyn Clock>,
+	global: Arc<Bucket>,
+	/// Bucket hash for each route key which has been seen.
+	routes: Mutex<BTreeMap<String, String>>,
+	/// Limits for each bucket and major parameter, or unbucketed route key.
+	buckets: Mutex<BTreeMap<String, Arc<Bucket>>>,
+}
+
+impl Default for RateLimits {
+	fn default() -> RateLimits {
+		RateLimits::with_clock(Arc::new(SystemClock))
+	}
 }
 
 impl RateLimits {
-	/// Check before issuing a request for the given URL.
-	pub fn pre_check(&self, url: &str) {
-		self.global
+	fn with_clock(clock: Arc<dyn Clock>) -> RateLimits {
+		RateLimits {
+			clock: clock,
+			global: Arc::default(),
+			routes: Mutex::default(),
+			buckets: Mutex::default(),
+		}
+	}
+
+	/// Wait until a request for the given method and URL may be issued.
+	pub fn pre_check(&self, method: &str, url: &str) {
+		self.global.acquire(&*self.clock);
+		self.bucket(&Route::new(method, url)).acquire(&*self.clock);
+	}
+
+	/// Wait until a request for the given method and URL may be issued, as
+	/// `pre_check` does, but without blocking the thread.
+	///
+	/// Must be polled from within a tokio runtime.
+	#[cfg(feature = "async")]
+	pub fn wait(limits: &Arc<RateLimits>, method: &str, url: &str) -> Wait {
+		Wait {
+			limits: limits.clone(),
+			route: Route::new(method, url),
+			global: false,
+			turn: None,
+		}
+	}
+
+	/// Update based on rate limit headers in the response for the given method
+	/// and URL. Returns `true` if the request was rate limited and should be
+	/// retried after calling `pre_check` again.
+	pub fn post_update(&self, method: &str, url: &str, response: &hyper::client::Response) -> bool {
+		self.update(method, url, response.status, &response.headers)
+	}
+
+	/// Update based on the status and headers of a response, as `post_update`
+	/// does.
+	pub fn update(&self, method: &str, url: &str, status: StatusCode, headers: &Headers) -> bool {
+		let now = self.clock.now();
+		if headers.get_raw("X-RateLimit-Global").is_some() {
+			return self.global.update(now, status, headers);
+		}
+		let route = Route::new(method, url);
+		if let Some(Ok(bucket)) = headers
+			.get_raw("X-RateLimit-Bucket")
+			.and_then(|values| values.first())
+			.map(|value| std::str::from_utf8(value))
+		{
+			self.routes
+				.lock()
+				.expect("Rate limits poisoned")
+				.insert(route.key.clone(), bucket.to_owned());
+		}
+		self.bucket(&route).update(now, status, headers)
+	}
+
+	fn bucket(&self, route: &Route) -> Arc<Bucket> {
+		let key = match self
+			.routes
 			.lock()
 			.expect("Rate limits poisoned")
-			.pre_check();
-		if let Some(rl) = self
-			.endpoints
+			.get(&route.key)
+		{
+			Some(bucket) => format!("{}:{}", bucket, route.major),
+			None => format!("{}:{}", route.key, route.major),
+		};
+		self.buckets
 			.lock()
 			.expect("Rate limits poisoned")
-			.get_mut(url)
-		{
-			rl.pre_check();
+			.entry(key)
+			.or_insert_with(Arc::default)
+			.clone()
+	}
+}
+
+/// A request's route, with every ID and token replaced, and its major
+/// parameter.
+///
+/// `DELETE https://discord.com/api/v6/channels/1/messages/2` has template
+/// `/channels/:id/messages/:id` and major parameter `channels/1`. The method
+/// is part of the key since, for example, deleting messages is limited
+/// separately from editing them.
+struct Route {
+	key: String,
+	template: String,
+	major: String,
+	/// The path and query with only tokens replaced, safe to log
+	redacted: String,
+}
+
+impl Route {
+	fn new(method: &str, url: &str) -> Route {
+		let path = api_path(url);
+		let segments: Vec<&str> = path.split('/').collect();
+		let mut major = String::new();
+		let mut template = Vec::with_capacity(segments.len());
+		let mut redacted = Vec::with_capacity(segments.len());
+		for (i, &segment) in segments.iter().enumerate() {
+			let prev = if i > 0 { segments[i - 1] } else { "" };
+			let prev2 = if i > 1 { segments[i - 2] } else { "" };
+			let is_token = prev2 == "interactions" || prev2 == "webhooks";
+			redacted.push(if is_token { ":token" } else { segment });
+			template.push(match prev {
+				"channels" | "guilds" if major.is_empty() && is_id(segment) => {
+					major = format!("{}/{}", prev, segment);
+					":id"
+				}
+				// Webhooks used with their token are limited per token
+				"webhooks" if major.is_empty() && is_id(segment) => {
+					major = match segments.get(i + 1) {
+						Some(token) => format!("webhooks/{}/{}", segment, token),
+						None => format!("webhooks/{}", segment),
+					};
+					":id"
+				}
+				// Reactions share a limit regardless of emoji or user
+				"reactions" => ":emoji",
+				"invite" | "invites" | "templates" => ":code",
+				_ if is_token => ":token",
+				_ if is_id(segment) => ":id",
+				_ => segment,
+			});
+		}
+		let template = template.join("/");
+		let mut redacted = redacted.join("/");
+		if let Some(query) = url.splitn(2, '?').nth(1) {
+			redacted.push('?');
+			redacted.push_str(query);
+		}
+		Route {
+			key: format!("{} {}", method.to_uppercase(), template),
+			template: template,
+			major: major,
+			redacted: redacted,
 		}
 	}
+}
 
-	/// Update based on rate limit headers in the response for given URL.
-	/// Returns `true` if the request was rate limited and should be retried.
-	pub fn post_update(&self, url: &str, response: &hyper::client::Response) -> bool {
-		if response.headers.get_raw("X-RateLimit-Global").is_some() {
-			self.global
-				.lock()
-				.expect("Rate limits poisoned")
-				.post_update(response)
-		} else {
-			self.endpoints
-				.lock()
-				.expect("Rate limits poisoned")
-				.entry(url.to_owned())
-				.or_insert_with(RateLimit::default)
-				.post_update(response)
+/// The path of a URL relative to the API base, without its query or a
+/// version such as `/v6`.
+fn api_path(url: &str) -> &str {
+	let path = url.split('?').next().unwrap_or(url);
+	match path.find("/api") {
+		Some(index) => {
+			let rest = &path[index + "/api".len()..];
+			match rest.get(1..).and_then(|r| r.split('/').next()) {
+				Some(version) if rest.starts_with("/v") && version.len() > 1 && is_id(&version[1..]) => {
+					&rest[1 + version.len()..]
+				}
+				_ => rest,
+			}
+		}
+		None => path,
+	}
+}
+
+fn is_id(segment: &str) -> bool {
+	!segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// The route template of a URL, relative to the API base, with every ID and
+/// token replaced. `https://discord.com/api/v6/channels/1/messages/2` becomes
+/// `/channels/:id/messages/:id`.
+///
+/// This is the route which rate limit buckets are learned for, and never
+/// contains webhook or interaction tokens.
+pub fn route_template(url: &str) -> String {
+	Route::new("", url).template
+}
+
+/// The path and query of a URL, relative to the API base, with webhook and
+/// interaction tokens replaced but IDs kept.
+pub fn redacted_path(url: &str) -> String {
+	Route::new("", url).redacted
+}
+
+/// A single rate limit, with a queue of the requests waiting on it.
+#[derive(Default)]
+struct Bucket {
+	state: Mutex<RateLimit>,
+	turn: Condvar,
+}
+
+impl Bucket {
+	/// Wait for this request's turn, then for the limit to allow it.
+	fn acquire(&self, clock: &dyn Clock) {
+		let mut state = self.state.lock().expect("Rate limits poisoned");
+		let ticket = state.next_ticket;
+		state.next_ticket += 1;
+		while state.serving != ticket {
+			state = self.turn.wait(state).expect("Rate limits poisoned");
+		}
+		// Responses may arrive while sleeping, so check again after each wait
+		while let Some(deadline) = state.take(clock.now()) {
+			drop(state);
+			if let Some(delay) = deadline.checked_duration_since(clock.now()) {
+				warn!("pre-ratelimit: sleeping for {}ms", duration_ms(delay));
+			}
+			clock.sleep_until(deadline);
+			state = self.state.lock().expect("Rate limits poisoned");
+		}
+		self.pass_turn(state);
+	}
+
+	/// Join the queue, returning this request's ticket.
+	#[cfg(feature = "async")]
+	fn ticket(&self) -> u64 {
+		let mut state = self.state.lock().expect("Rate limits poisoned");
+		state.next_ticket += 1;
+		state.next_ticket - 1
+	}
+
+	/// Give the turn to the next request in the queue which is still waiting.
+	fn pass_turn(&self, mut state: MutexGuard<RateLimit>) {
+		state.serving += 1;
+		loop {
+			let serving = state.serving;
+			if !state.abandoned.remove(&serving) {
+				break;
+			}
+			state.serving += 1;
+		}
+		let wakers = mem::replace(&mut state.wakers, Vec::new());
+		drop(state);
+		self.turn.notify_all();
+		for waker in wakers {
+			waker.wake();
+		}
+	}
+
+	fn update(&self, now: Instant, status: StatusCode, headers: &Headers) -> bool {
+		let mut state = self.state.lock().expect("Rate limits poisoned");
+		match state.update(now, status, headers) {
+			Err(e) => {
+				error!("rate limit checking error: {}", e);
+				false
+			}
+			Ok(r) => r,
 		}
 	}
 }
 
 #[derive(Default)]
 struct RateLimit {
-	reset: i64,
+	reset: Option<Instant>,
 	limit: i64,
 	remaining: i64,
+	/// The ticket which the next request to arrive will take.
+	next_ticket: u64,
+	/// The ticket of the request whose turn it is.
+	serving: u64,
+	/// Tickets of async requests which were cancelled while queued.
+	abandoned: BTreeSet<u64>,
+	/// Async requests to wake when the turn passes.
+	wakers: Vec<Waker>,
 }
 
 impl RateLimit {
-	fn pre_check(&mut self) {
+	/// Deduct a request from the limit, or return when to try again.
+	fn take(&mut self, now: Instant) -> Option<Instant> {
 		// break out if uninitialized
-		if self.limit == 0 {
-			return;
-		}
+		let reset = self.reset?;
 
-		let difference = self.reset - Utc::now().timestamp();
-		if difference < 0 {
-			// If reset is apparently in the past, optimistically assume that
-			// the reset has occurred and we're good for the next three seconds
-			// or so. When the response comes back we will know for real.
-			self.reset += 3;
-			self.remaining = self.limit;
-			return;
+		if reset <= now {
+			if self.limit == 0 {
+				// Only waiting out a 429, with no limit otherwise known
+				self.reset = None;
+				return None;
+			}
+			// If reset is in the past, optimistically assume that the reset
+			// has occurred and we're good for the next three seconds or so.
+			// When the response comes back we will know for real.
+			self.reset = Some(now + Duration::from_secs(3));
+			self.remaining = self.limit - 1;
+			return None;
 		}
 
-		// if no requests remain, wait a bit
+		// if no requests remain, wait until the reset
 		if self.remaining <= 0 {
-			// 900ms in case "difference" is off by 1
-			let delay = difference as u64 * 1000 + 900;
-			warn!("pre-ratelimit: sleeping for {}ms", delay);
-			::sleep_ms(delay);
-			return;
+			return Some(reset);
 		}
 
 		// Deduct from our remaining requests. If a lot of requests are issued
 		// before any responses are received, this will mean we can still limit
 		// preemptively.
 		self.remaining -= 1;
+		None
 	}
 
-	fn post_update(&mut self, response: &hyper::client::Response) -> bool {
-		match self.try_post_update(response) {
-			Err(e) => {
-				error!("rate limit checking error: {}", e);
-				false
+	fn update(&mut self, now: Instant, status: StatusCode, headers: &Headers) -> Result<bool> {
+		// Reset-After is relative, and so immune to clock skew
+		if let Some(after) = read_header(headers, "X-RateLimit-Reset-After")? {
+			self.reset = Some(now + Duration::from_millis((after * 1000.).ceil() as u64));
+		} else if let Some(reset) = read_header(headers, "X-RateLimit-Reset")? {
+			let after = reset - Utc::now().timestamp_millis() as f64 / 1000.;
+			self.reset = Some(now + Duration::from_millis((after.max(0.) * 1000.).ceil() as u64));
+		}
+		if let Some(limit) = read_header(headers, "X-RateLimit-Limit")? {
+			self.limit = limit as i64;
+		}
+		if let Some(remaining) = read_header(headers, "X-RateLimit-Remaining")? {
+			self.remaining = remaining as i64;
+		}
+		if status == StatusCode::TooManyRequests {
+			// Both are in seconds, but Reset-After is more precise
+			let after = match read_header(headers, "X-RateLimit-Reset-After")? {
+				Some(after) => Some(after),
+				None => read_header(headers, "Retry-After")?,
+			};
+			if let Some(after) = after {
+				let delay = (after * 1000.).ceil() as u64 + 100; // 100ms of leeway
+				warn!("429: retrying in {}ms", delay);
+				let retry_at = now + Duration::from_millis(delay);
+				self.reset = Some(self.reset.map_or(retry_at, |reset| reset.max(retry_at)));
+				self.remaining = 0;
+				return Ok(true); // retry the request
 			}
-			Ok(r) => r,
 		}
+		Ok(false)
 	}
+}
+
+/// A future which resolves once a request may be issued, returned by
+/// `RateLimits::wait`.
+///
+/// Dropping it gives up its place in the queue.
+#[cfg(feature = "async")]
+pub struct Wait {
+	limits: Arc<RateLimits>,
+	route: Route,
+	/// Whether the global limit has been passed, and the route's is next.
+	global: bool,
+	turn: Option<Turn>,
+}
+
+#[cfg(feature = "async")]
+impl Future for Wait {
+	type Output = ();
 
-	fn try_post_update(&mut self, response: &hyper::client::Response) -> Result<bool> {
-		if let Some(reset) = read_header(&response.headers, "X-RateLimit-Reset")? {
-			self.reset = reset;
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+		let this = &mut *self;
+		loop {
+			if this.turn.is_none() {
+				let bucket = if this.global {
+					this.limits.bucket(&this.route)
+				} else {
+					this.limits.global.clone()
+				};
+				let ticket = bucket.ticket();
+				this.turn = Some(Turn {
+					bucket: bucket,
+					ticket: ticket,
+					sleep: None,
+					done: false,
+				});
+			}
+			if let Some(ref mut turn) = this.turn {
+				if turn.poll(&*this.limits.clock, cx).is_pending() {
+					return Poll::Pending;
+				}
+			}
+			this.turn = None;
+			if this.global {
+				return Poll::Ready(());
+			}
+			this.global = true;
 		}
-		if let Some(limit) = read_header(&response.headers, "X-RateLimit-Limit")? {
-			self.limit = limit;
+	}
+}
+
+/// An async request's place in a bucket's queue.
+#[cfg(feature = "async")]
+struct Turn {
+	bucket: Arc<Bucket>,
+	ticket: u64,
+	sleep: Option<Pin<Box<::tokio::time::Sleep>>>,
+	done: bool,
+}
+
+#[cfg(feature = "async")]
+impl Turn {
+	/// Wait for this request's turn, then for the limit to allow it, as
+	/// `Bucket::acquire` does.
+	fn poll(&mut self, clock: &dyn Clock, cx: &mut Context) -> Poll<()> {
+		loop {
+			if let Some(ref mut sleep) = self.sleep {
+				if sleep.as_mut().poll(cx).is_pending() {
+					return Poll::Pending;
+				}
+			}
+			self.sleep = None;
+			let mut state = self.bucket.state.lock().expect("Rate limits poisoned");
+			if state.serving != self.ticket {
+				state.wakers.push(cx.waker().clone());
+				return Poll::Pending;
+			}
+			match state.take(clock.now()) {
+				Some(deadline) => {
+					let delay = deadline.saturating_duration_since(clock.now());
+					warn!("pre-ratelimit: sleeping for {}ms", duration_ms(delay));
+					self.sleep = Some(Box::pin(::tokio::time::sleep(delay)));
+				}
+				None => {
+					self.done = true;
+					self.bucket.pass_turn(state);
+					return Poll::Ready(());
+				}
+			}
 		}
-		if let Some(remaining) = read_header(&response.headers, "X-RateLimit-Remaining")? {
-			self.remaining = remaining;
+	}
+}
+
+#[cfg(feature = "async")]
+impl Drop for Turn {
+	fn drop(&mut self) {
+		if self.done {
+			return;
 		}
-		if response.status == hyper::status::StatusCode::TooManyRequests {
-			if let Some(delay) = read_header(&response.headers, "Retry-After")? {
-				let delay = delay as u64 + 100; // 100ms of leeway
-				warn!("429: sleeping for {}ms", delay);
-				::sleep_ms(delay);
-				return Ok(true); // retry the request
+		if let Ok(mut state) = self.bucket.state.lock() {
+			if state.serving == self.ticket {
+				self.bucket.pass_turn(state);
+			} else {
+				state.abandoned.insert(self.ticket);
 			}
 		}
-		Ok(false)
 	}
 }
 
-fn read_header(headers: &hyper::header::Headers, name: &str) -> Result<Option<i64>> {
+fn duration_ms(duration: Duration) -> u64 {
+	duration.as_secs() * 1000 + duration.subsec_nanos() as u64 / 1_000_000
+}
+
+fn read_header(headers: &hyper::header::Headers, name: &str) -> Result<Option<f64>> {
 	match headers.get_raw(name) {
 		Some(hdr) => {
 			if hdr.len() == 1 {
 				match std::str::from_utf8(&hdr[0]) {
-					Ok(text) => match text.parse::<i64>() {
+					Ok(text) => match text.parse::<f64>() {
 						Ok(val) => Ok(Some(val)),
-						Err(_) => match text.parse::<f64>() {
-							Ok(val) => Ok(Some(val as i64)),
-							Err(_) => Err(Error::Other("header is not an i64 or f64")),
-						},
+						Err(_) => Err(Error::Other("header is not a number")),
 					},
 					Err(_) => Err(Error::Other("header is not UTF-8")),
 				}
@@ -141,3 +519,219 @@ fn read_header(headers: &hyper::header::Headers, name: &str) -> Result<Option<i6
 		None => Ok(None),
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use std::sync::mpsc;
+	use std::thread;
+
+	fn route(method: &str, url: &str) -> (String, String) {
+		let route = Route::new(method, url);
+		(route.key, route.major)
+	}
+
+	#[test]
+	fn major_parameters() {
+		assert_eq!(
+			route("delete", "https://discord.com/api/v6/channels/10/messages/20"),
+			("DELETE /channels/:id/messages/:id".into(), "channels/10".into())
+		);
+		assert_eq!(
+			route("put", "/api/channels/10/messages/20/reactions/%F0%9F%91%8D/@me"),
+			("PUT /channels/:id/messages/:id/reactions/:emoji/@me".into(), "channels/10".into())
+		);
+		assert_eq!(
+			route("get", "/api/guilds/5/members?limit=1000&after=30"),
+			("GET /guilds/:id/members".into(), "guilds/5".into())
+		);
+		assert_eq!(
+			route("get", "/api/guilds/templates/abc"),
+			("GET /guilds/templates/:code".into(), "".into())
+		);
+		assert_eq!(
+			route("post", "/api/webhooks/7/tok/messages/8?wait=true"),
+			("POST /webhooks/:id/:token/messages/:id".into(), "webhooks/7/tok".into())
+		);
+		assert_eq!(
+			route("post", "/api/interactions/9/tok/callback"),
+			("POST /interactions/:id/:token/callback".into(), "".into())
+		);
+	}
+
+	#[test]
+	fn route_templates() {
+		assert_eq!(
+			route_template("https://discord.com/api/v6/channels/10/messages/20/reactions/x/@me"),
+			"/channels/:id/messages/:id/reactions/:emoji/@me"
+		);
+		assert_eq!(route_template("http://127.0.0.1:80/api/webhooks/7/tok?wait=true"), "/webhooks/:id/:token");
+		assert_eq!(route_template("https://discord.com/api/v6/invites/abc"), "/invites/:code");
+		assert_eq!(
+			redacted_path("https://discord.com/api/v6/webhooks/7/tok/messages/8?wait=true"),
+			"/webhooks/7/:token/messages/8?wait=true"
+		);
+	}
+
+	/// A clock which only moves when told to.
+	struct FakeClock {
+		time: Mutex<(Instant, usize)>,
+		changed: Condvar,
+	}
+
+	impl FakeClock {
+		fn new() -> Arc<FakeClock> {
+			Arc::new(FakeClock {
+				time: Mutex::new((Instant::now(), 0)),
+				changed: Condvar::new(),
+			})
+		}
+
+		fn sleepers(&self) -> usize {
+			self.time.lock().unwrap().1
+		}
+
+		fn advance(&self, duration: Duration) {
+			self.time.lock().unwrap().0 += duration;
+			self.changed.notify_all();
+		}
+	}
+
+	impl Clock for FakeClock {
+		fn now(&self) -> Instant {
+			self.time.lock().unwrap().0
+		}
+
+		fn sleep_until(&self, deadline: Instant) {
+			let mut time = self.time.lock().unwrap();
+			time.1 += 1;
+			self.changed.notify_all();
+			while time.0 < deadline {
+				time = self.changed.wait(time).unwrap();
+			}
+			time.1 -= 1;
+		}
+	}
+
+	/// Block until `condition` holds, failing the test after five seconds.
+	fn wait_for<F: Fn() -> bool>(condition: F) {
+		let start = Instant::now();
+		while !condition() {
+			assert!(start.elapsed() < Duration::from_secs(5), "timed out");
+			thread::sleep(Duration::from_millis(1));
+		}
+	}
+
+	fn exhaust(limits: &RateLimits, url: &str, reset_after: &str) {
+		let mut headers = Headers::new();
+		headers.set_raw("X-RateLimit-Bucket", vec![b"abcd".to_vec()]);
+		headers.set_raw("X-RateLimit-Limit", vec![b"1".to_vec()]);
+		headers.set_raw("X-RateLimit-Remaining", vec![b"0".to_vec()]);
+		headers.set_raw("X-RateLimit-Reset-After", vec![reset_after.as_bytes().to_vec()]);
+		limits.update("post", url, StatusCode::Ok, &headers);
+	}
+
+	#[test]
+	fn too_many_requests_waits_seconds() {
+		let clock = FakeClock::new();
+		let limits = RateLimits::with_clock(clock.clone());
+		let start = clock.now();
+
+		let mut headers = Headers::new();
+		headers.set_raw("X-RateLimit-Global", vec![b"true".to_vec()]);
+		headers.set_raw("Retry-After", vec![b"2".to_vec()]);
+		assert!(limits.update("post", "/channels/1/messages", StatusCode::TooManyRequests, &headers));
+		let reset = limits.global.state.lock().unwrap().reset;
+		assert_eq!(reset, Some(start + Duration::from_millis(2100)));
+
+		let mut headers = Headers::new();
+		headers.set_raw("Retry-After", vec![b"1".to_vec()]);
+		headers.set_raw("X-RateLimit-Reset-After", vec![b"0.25".to_vec()]);
+		assert!(limits.update("post", "/channels/1/messages", StatusCode::TooManyRequests, &headers));
+		let bucket = limits.bucket(&Route::new("post", "/channels/1/messages"));
+		assert_eq!(bucket.state.lock().unwrap().reset, Some(start + Duration::from_millis(350)));
+	}
+
+	#[test]
+	fn throttled_bucket_does_not_block_others() {
+		let clock = FakeClock::new();
+		let limits = Arc::new(RateLimits::with_clock(clock.clone()));
+		exhaust(&limits, "/channels/1/messages", "10");
+
+		let (tx, rx) = mpsc::channel();
+		let waiting = limits.clone();
+		thread::spawn(move || {
+			waiting.pre_check("post", "/channels/1/messages");
+			tx.send(()).unwrap();
+		});
+		wait_for(|| clock.sleepers() == 1);
+
+		// Other channels and routes proceed while the first bucket is waited on
+		limits.pre_check("post", "/channels/2/messages");
+		limits.pre_check("get", "/channels/1/messages");
+		assert!(rx.try_recv().is_err());
+
+		clock.advance(Duration::from_secs(10));
+		rx.recv_timeout(Duration::from_secs(5)).unwrap();
+	}
+
+	#[test]
+	fn bucket_queue_is_fair() {
+		let clock = FakeClock::new();
+		let limits = Arc::new(RateLimits::with_clock(clock.clone()));
+		exhaust(&limits, "/channels/1/messages", "1");
+		let bucket = limits.bucket(&Route::new("post", "/channels/1/messages"));
+
+		let order = Arc::new(Mutex::new(Vec::new()));
+		for i in 0..4 {
+			let limits = limits.clone();
+			let order = order.clone();
+			thread::spawn(move || {
+				limits.pre_check("post", "/channels/1/messages");
+				order.lock().unwrap().push(i);
+			});
+			// Let each request join the queue before starting the next
+			wait_for(|| bucket.state.lock().unwrap().next_ticket == i + 1);
+		}
+
+		// One request is let through per reset, in arrival order
+		let start = Instant::now();
+		while order.lock().unwrap().len() < 4 {
+			assert!(start.elapsed() < Duration::from_secs(5), "timed out");
+			if clock.sleepers() > 0 {
+				clock.advance(Duration::from_secs(3));
+			}
+			thread::sleep(Duration::from_millis(1));
+		}
+		assert_eq!(*order.lock().unwrap(), vec![0, 1, 2, 3]);
+	}
+
+	#[cfg(feature = "async")]
+	#[test]
+	fn cancelled_waits_give_up_their_turn() {
+		use futures_util::task::noop_waker_ref;
+		use std::task::Context;
+
+		let runtime = ::tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap();
+		let _runtime = runtime.enter();
+		let clock = FakeClock::new();
+		let limits = Arc::new(RateLimits::with_clock(clock.clone()));
+		exhaust(&limits, "/channels/1/messages", "10");
+
+		let mut cx = Context::from_waker(noop_waker_ref());
+		let mut waits: Vec<_> = (0..3).map(|_| RateLimits::wait(&limits, "post", "/channels/1/messages")).collect();
+		for wait in &mut waits {
+			assert!(Pin::new(wait).poll(&mut cx).is_pending());
+		}
+
+		// Neither the request being served nor one queued behind it holds up
+		// the rest of the queue when dropped
+		let mut last = waits.pop().unwrap();
+		drop(waits);
+		clock.advance(Duration::from_secs(10));
+		assert!(Pin::new(&mut last).poll(&mut cx).is_ready());
+		let bucket = limits.bucket(&Route::new("post", "/channels/1/messages"));
+		let state = bucket.state.lock().unwrap();
+		assert_eq!(state.serving, state.next_ticket);
+	}
+}
@@ -0,0 +1,325 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io;
+use std::time::Duration;
+
+use hyper;
+use hyper::status::StatusCode;
+
+/// Controls when and how often failed REST requests are repeated.
+///
+/// By default, a request is attempted up to three times, with exponential
+/// backoff between attempts, when it is rate limited, when Discord responds
+/// with `502 Bad Gateway`, `503 Service Unavailable` or `504 Gateway Timeout`,
+/// or when the connection is reset. Only rate limited requests, which Discord
+/// did not act on, are retried regardless of method; otherwise `POST` and
+/// `PATCH` requests, which may have taken effect, are attempted once unless
+/// `retry_non_idempotent` is set. Independently of the policy, a request is
+/// always resent once if its pooled connection turns out to have gone stale.
+/// Configure it for all requests with `DiscordBuilder::with_retry_policy`, or
+/// for some calls only with `Discord::with_retry_policy`:
+///
+/// ```ignore
+/// // Never repeat a payment notice, even if its outcome is unknown
+/// discord.with_retry_policy(RetryPolicy::never()).send_message(channel, text, "", false)?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+	max_attempts: u32,
+	base_delay: Duration,
+	max_delay: Duration,
+	jitter: bool,
+	rate_limited: bool,
+	server_errors: bool,
+	io_errors: bool,
+	non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+	fn default() -> RetryPolicy {
+		RetryPolicy {
+			max_attempts: 3,
+			base_delay: Duration::from_millis(500),
+			max_delay: Duration::from_secs(10),
+			jitter: true,
+			rate_limited: true,
+			server_errors: true,
+			io_errors: true,
+			non_idempotent: false,
+		}
+	}
+}
+
+impl RetryPolicy {
+	/// A policy under which requests are attempted once, other than being
+	/// resent on a stale connection.
+	pub fn never() -> RetryPolicy {
+		RetryPolicy::default().max_attempts(1)
+	}
+
+	/// Set the maximum number of attempts, including the first. Defaults to 3.
+	pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+		self.max_attempts = max_attempts.max(1);
+		self
+	}
+
+	/// Set the delay before the first retry, which doubles for each
+	/// subsequent retry up to `max_delay`. Defaults to 500ms.
+	pub fn base_delay(mut self, base_delay: Duration) -> Self {
+		self.base_delay = base_delay;
+		self
+	}
+
+	/// Set the longest delay between attempts. Defaults to 10 seconds.
+	pub fn max_delay(mut self, max_delay: Duration) -> Self {
+		self.max_delay = max_delay;
+		self
+	}
+
+	/// Set whether delays are randomly shortened by up to half, so that
+	/// clients failing together do not retry together. Defaults to true.
+	pub fn jitter(mut self, jitter: bool) -> Self {
+		self.jitter = jitter;
+		self
+	}
+
+	/// Set whether `429 Too Many Requests` responses are retried, once the
+	/// rate limit allows. Defaults to true.
+	pub fn retry_rate_limited(mut self, retry: bool) -> Self {
+		self.rate_limited = retry;
+		self
+	}
+
+	/// Set whether 502, 503 and 504 responses are retried. Defaults to true.
+	pub fn retry_server_errors(mut self, retry: bool) -> Self {
+		self.server_errors = retry;
+		self
+	}
+
+	/// Set whether connections which are reset, aborted or time out are
+	/// retried. Defaults to true.
+	pub fn retry_io_errors(mut self, retry: bool) -> Self {
+		self.io_errors = retry;
+		self
+	}
+
+	/// Set whether `POST` and `PATCH` requests are retried after server and
+	/// connection errors, in which case they may take effect twice. Defaults
+	/// to false.
+	pub fn retry_non_idempotent(mut self, retry: bool) -> Self {
+		self.non_idempotent = retry;
+		self
+	}
+
+	/// Whether another attempt may follow the given attempt, counting from 1.
+	pub(crate) fn allows(&self, attempt: u32) -> bool {
+		attempt < self.max_attempts
+	}
+
+	pub(crate) fn retries_rate_limited(&self) -> bool {
+		self.rate_limited
+	}
+
+	/// Whether the outcome of a request which was not rate limited should be
+	/// retried, after waiting for `backoff`.
+	pub(crate) fn retries(&self, method: &str, result: &hyper::Result<hyper::client::Response>) -> bool {
+		match *result {
+			Ok(ref response) => self.retries_status(method, response.status),
+			Err(hyper::Error::Io(ref err)) => self.retries_io_error(method, err),
+			Err(_) => false,
+		}
+	}
+
+	/// Whether a request which received a response with the given status
+	/// should be retried.
+	pub(crate) fn retries_status(&self, method: &str, status: StatusCode) -> bool {
+		self.server_errors
+			&& self.may_repeat(method)
+			&& match status {
+				StatusCode::BadGateway | StatusCode::ServiceUnavailable | StatusCode::GatewayTimeout => true,
+				_ => false,
+			}
+	}
+
+	/// Whether a request which failed with the given I/O error should be
+	/// retried.
+	pub(crate) fn retries_io_error(&self, method: &str, err: &io::Error) -> bool {
+		self.io_errors
+			&& self.may_repeat(method)
+			&& match err.kind() {
+				io::ErrorKind::ConnectionReset
+				| io::ErrorKind::ConnectionAborted
+				| io::ErrorKind::BrokenPipe
+				| io::ErrorKind::TimedOut
+				| io::ErrorKind::UnexpectedEof => true,
+				_ => false,
+			}
+	}
+
+	/// Whether a request with the given method may be sent again after it
+	/// may have taken effect.
+	fn may_repeat(&self, method: &str) -> bool {
+		match &method.to_uppercase()[..] {
+			"POST" | "PATCH" => self.non_idempotent,
+			_ => true,
+		}
+	}
+
+	/// The delay after the given failed attempt, counting from 1.
+	pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+		let factor = 1u32 << attempt.saturating_sub(1).min(16);
+		let delay = (self.base_delay * factor).min(self.max_delay);
+		if self.jitter {
+			// A fresh RandomState is randomly seeded, which is enough here
+			let random = RandomState::new().build_hasher().finish();
+			delay / 2 + (delay / 2).mul_f64((random % 1000) as f64 / 1000.)
+		} else {
+			delay
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use hyper::net::NetworkStream;
+	use model::{ChannelId, MessageId};
+	use std::io::{BufRead, BufReader, Read, Write};
+	use std::net::TcpListener;
+	use std::sync::atomic::{AtomicBool, Ordering};
+	use std::sync::mpsc;
+	use std::thread;
+	use test_support::{mock_discord, mock_responses, mock_server, MESSAGE};
+	use {DiscordBuilder, Transport};
+
+	#[test]
+	fn exponential_backoff() {
+		let policy = RetryPolicy::default()
+			.base_delay(Duration::from_millis(100))
+			.max_delay(Duration::from_millis(350))
+			.jitter(false);
+		let delays: Vec<_> = (1..5).map(|attempt| policy.backoff(attempt)).collect();
+		assert_eq!(delays, vec![100, 200, 350, 350].into_iter().map(Duration::from_millis).collect::<Vec<_>>());
+
+		let policy = policy.jitter(true);
+		for attempt in 1..5 {
+			let delay = policy.backoff(attempt);
+			assert!(delay >= delays[attempt as usize - 1] / 2 && delay <= delays[attempt as usize - 1]);
+		}
+		assert!(!RetryPolicy::never().allows(1));
+
+		let reset = || Err(hyper::Error::Io(io::Error::new(io::ErrorKind::ConnectionAborted, "closed")));
+		assert!(policy.retries("get", &reset()));
+		assert!(policy.retries("delete", &reset()));
+		assert!(!policy.retries("post", &reset()));
+		assert!(policy.clone().retry_non_idempotent(true).retries("post", &reset()));
+	}
+
+	#[test]
+	fn retried_requests() {
+		let policy = RetryPolicy::default().base_delay(Duration::from_millis(1));
+		let (base, requests) = mock_responses(vec![
+			("503 Service Unavailable", String::new()),
+			("502 Bad Gateway", String::new()),
+			("200 OK", MESSAGE.to_owned()),
+		]);
+		let message = mock_discord(&base)
+			.with_retry_policy(policy.clone())
+			.get_message(ChannelId(3), MessageId(2))
+			.unwrap();
+		assert_eq!(message.id, MessageId(2));
+		assert_eq!(requests.iter().count(), 3);
+
+		// Requests which may have taken effect are only retried if asked
+		let (base, requests) = mock_responses(vec![
+			("503 Service Unavailable", String::new()),
+			("200 OK", MESSAGE.to_owned()),
+		]);
+		let discord = mock_discord(&base).with_retry_policy(policy.clone());
+		assert!(discord.send_message(ChannelId(3), "hi", "", false).is_err());
+		assert!(requests.recv().is_ok());
+		assert!(requests.try_recv().is_err());
+
+		let (base, requests) = mock_responses(vec![
+			("503 Service Unavailable", String::new()),
+			("200 OK", MESSAGE.to_owned()),
+		]);
+		let discord = mock_discord(&base).with_retry_policy(policy.retry_non_idempotent(true));
+		assert!(discord.send_message(ChannelId(3), "hi", "", false).is_ok());
+		assert_eq!(requests.iter().count(), 2);
+
+		// A connection closed without a response is not resent under never()
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let base = format!("http://{}/api", listener.local_addr().unwrap());
+		let (tx, connections) = mpsc::channel();
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				let mut line = String::new();
+				let _ = BufReader::new(stream.unwrap()).read_line(&mut line);
+				if tx.send(line).is_err() {
+					break;
+				}
+			}
+		});
+		let result = mock_discord(&base)
+			.with_retry_policy(RetryPolicy::never())
+			.get_message(ChannelId(3), MessageId(2));
+		assert!(result.is_err());
+		assert_eq!(connections.try_iter().count(), 1);
+
+		// But a stale connection is, whatever the method and policy
+		let (base, requests) = mock_server("200 OK", MESSAGE);
+		let discord = DiscordBuilder::new()
+			.with_api_base(&base)
+			.with_transport(StaleOnce(AtomicBool::new(true)))
+			.with_retry_policy(RetryPolicy::never())
+			.from_bot_token("token")
+			.unwrap();
+		assert!(discord.send_message(ChannelId(3), "hi", "", false).is_ok());
+		assert!(requests.recv().unwrap().starts_with("POST /api/channels/3/messages HTTP/1.1\r\n"));
+	}
+
+	/// A transport whose first connection turns out to have been aborted.
+	struct StaleOnce(AtomicBool);
+
+	impl Transport for StaleOnce {
+		fn connect(&self, host: &str, port: u16, scheme: &str) -> hyper::Result<Box<dyn NetworkStream + Send>> {
+			if self.0.swap(false, Ordering::SeqCst) {
+				return Ok(Box::new(Aborted));
+			}
+			Transport::connect(&hyper::net::HttpConnector, host, port, scheme)
+		}
+	}
+
+	struct Aborted;
+
+	impl Read for Aborted {
+		fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+			Err(io::ErrorKind::ConnectionAborted.into())
+		}
+	}
+
+	impl Write for Aborted {
+		fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+			Err(io::ErrorKind::ConnectionAborted.into())
+		}
+
+		fn flush(&mut self) -> io::Result<()> {
+			Err(io::ErrorKind::ConnectionAborted.into())
+		}
+	}
+
+	impl NetworkStream for Aborted {
+		fn peer_addr(&mut self) -> io::Result<::std::net::SocketAddr> {
+			Err(io::ErrorKind::ConnectionAborted.into())
+		}
+
+		fn set_read_timeout(&self, _: Option<Duration>) -> io::Result<()> {
+			Ok(())
+		}
+
+		fn set_write_timeout(&self, _: Option<Duration>) -> io::Result<()> {
+			Ok(())
+		}
+	}
+}
@@ -51,6 +51,14 @@ pub fn deserialize_id<'d, D: Deserializer<'d>>(d: D) -> Result<u64, D::Error> {
 	d.deserialize_any(IdVisitor)
 }
 
+/// Deserialize an optional maybe-string ID into a u64.
+pub fn deserialize_id_opt<'d, D: Deserializer<'d>>(d: D) -> Result<Option<u64>, D::Error> {
+	#[derive(Deserialize)]
+	struct Id(#[serde(deserialize_with = "deserialize_id")] u64);
+
+	Option::<Id>::deserialize(d).map(|id| id.map(|Id(id)| id))
+}
+
 /// Deserialize a maybe-string discriminator into a u16.
 /// Also enforces 0 <= N <= 9999.
 #[allow(unused_comparisons)]
@@ -160,6 +168,48 @@ pub mod reaction_emoji {
 	}
 }
 
+/// Support for `Component`, tagged by its numeric `type`.
+pub mod component {
+	use super::*;
+	use model::{Component, ComponentType};
+	use serde_json::{self, Value};
+
+	pub fn serialize<S: Serializer>(v: &Component, s: S) -> Result<S::Ok, S::Error> {
+		use serde::ser::Error;
+
+		let value = match *v {
+			Component::ActionRow(ref row) => serde_json::to_value(row),
+			Component::Button(ref button) => serde_json::to_value(button),
+			Component::SelectMenu(ref menu) => serde_json::to_value(menu),
+			Component::TextInput(ref input) => serde_json::to_value(input),
+			Component::Unknown(ref value) => return value.serialize(s),
+		};
+		let mut value = value.map_err(S::Error::custom)?;
+		if let (Value::Object(ref mut map), Some(kind)) = (&mut value, v.kind()) {
+			map.insert("type".into(), json!(kind));
+		}
+		value.serialize(s)
+	}
+
+	pub fn deserialize<'d, D: Deserializer<'d>>(d: D) -> Result<Component, D::Error> {
+		let value = Value::deserialize(d)?;
+		let kind = value
+			.get("type")
+			.and_then(|kind| ComponentType::deserialize(kind).ok());
+		Ok(match kind {
+			Some(ComponentType::ActionRow) => Component::ActionRow(from_value(value)?),
+			Some(ComponentType::Button) => Component::Button(from_value(value)?),
+			Some(ComponentType::TextInput) => Component::TextInput(from_value(value)?),
+			Some(_) => Component::SelectMenu(from_value(value)?),
+			None => Component::Unknown(value),
+		})
+	}
+
+	fn from_value<'d, T: Deserialize<'d>, E: Error>(value: Value) -> Result<T, E> {
+		T::deserialize(value).map_err(E::custom)
+	}
+}
+
 /// Support for named enums.
 pub mod named {
 	use super::*;
@@ -260,6 +310,40 @@ pub mod numeric {
 	}
 }
 macro_rules! serial_numbers {
+	// Variant for enums which keep unrecognized values in a tuple variant.
+	($typ:ident($unknown:ident); $($entry:ident, $value:expr;)*) => {
+		impl $typ {
+			/// Get the number Discord uses for this value.
+			pub fn num(&self) -> u64 {
+				match *self {
+					$($typ::$entry => $value,)*
+					$typ::$unknown(num) => num,
+				}
+			}
+
+			/// Get the value for the given number, which is `Unknown` if the
+			/// number is not recognized.
+			pub fn from_num(num: u64) -> Self {
+				match num {
+					$($value => $typ::$entry,)*
+					num => $typ::$unknown(num),
+				}
+			}
+		}
+		impl ::serial::numeric::NumericEnum for $typ {
+			fn num(&self) -> u64 {
+				self.num()
+			}
+
+			fn from_num(num: u64) -> Option<Self> {
+				Some(Self::from_num(num))
+			}
+
+			fn typename() -> &'static str {
+				stringify!($typ)
+			}
+		}
+	};
 	($typ:ident; $($entry:ident, $value:expr;)*) => {
 		impl $typ {
 			pub fn num(&self) -> u64 {
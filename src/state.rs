@@ -409,6 +409,71 @@ impl State {
 						srv.roles.retain(|r| r.id != *role_id);
 					});
 			}
+			Event::ServerEmojisUpdate(server_id, ref emojis) => {
+				self.servers
+					.iter_mut()
+					.find(|s| s.id == server_id)
+					.map(|srv| srv.emojis.clone_from(emojis));
+			}
+			Event::ServerStickersUpdate(server_id, ref stickers) => {
+				self.servers
+					.iter_mut()
+					.find(|s| s.id == server_id)
+					.map(|srv| srv.stickers.clone_from(stickers));
+			}
+			Event::ServerScheduledEventCreate(ref event) | Event::ServerScheduledEventUpdate(ref event) => {
+				self.servers
+					.iter_mut()
+					.find(|s| s.id == event.server_id)
+					.map(|srv| match srv.scheduled_events.iter_mut().find(|e| e.id == event.id) {
+						Some(existing) => {
+							// updates omit the subscriber count
+							let user_count = existing.user_count;
+							existing.clone_from(event);
+							if event.user_count.is_none() {
+								existing.user_count = user_count;
+							}
+						}
+						None => srv.scheduled_events.push(event.clone()),
+					});
+			}
+			Event::ServerScheduledEventDelete(ref event) => {
+				self.servers
+					.iter_mut()
+					.find(|s| s.id == event.server_id)
+					.map(|srv| srv.scheduled_events.retain(|e| e.id != event.id));
+			}
+			Event::ServerScheduledEventUserAdd { event_id, server_id, .. } => {
+				self.servers
+					.iter_mut()
+					.find(|s| s.id == server_id)
+					.and_then(|srv| srv.scheduled_events.iter_mut().find(|e| e.id == event_id))
+					.and_then(|event| event.user_count.as_mut())
+					.map(|count| *count += 1);
+			}
+			Event::ServerScheduledEventUserRemove { event_id, server_id, .. } => {
+				self.servers
+					.iter_mut()
+					.find(|s| s.id == server_id)
+					.and_then(|srv| srv.scheduled_events.iter_mut().find(|e| e.id == event_id))
+					.and_then(|event| event.user_count.as_mut())
+					.map(|count| *count = count.saturating_sub(1));
+			}
+			Event::StageInstanceCreate(ref stage) | Event::StageInstanceUpdate(ref stage) => {
+				self.servers
+					.iter_mut()
+					.find(|s| s.id == stage.server_id)
+					.map(|srv| match srv.stage_instances.iter_mut().find(|s| s.id == stage.id) {
+						Some(existing) => existing.clone_from(stage),
+						None => srv.stage_instances.push(stage.clone()),
+					});
+			}
+			Event::StageInstanceDelete(ref stage) => {
+				self.servers
+					.iter_mut()
+					.find(|s| s.id == stage.server_id)
+					.map(|srv| srv.stage_instances.retain(|s| s.id != stage.id));
+			}
 			Event::ChannelCreate(ref channel) => match *channel {
 				Channel::Group(ref group) => {
 					self.groups.insert(group.channel_id, group.clone());
@@ -510,6 +575,7 @@ impl State {
 						.find(|s| s.id == channel.server_id)
 						.map(|srv| {
 							srv.channels.retain(|c| c.id != channel.id);
+							srv.threads.retain(|t| t.parent_id != Some(channel.id));
 						});
 				}
 				Channel::Category(ref channel) => {
@@ -525,12 +591,94 @@ impl State {
 				Channel::News => {}
 				Channel::Store => {}
 			},
+			Event::ThreadCreate(ref thread) | Event::ThreadUpdate(ref thread) => {
+				self.servers
+					.iter_mut()
+					.find(|s| s.id == thread.server_id)
+					.map(|srv| {
+						let archived = thread.thread_metadata.as_ref().map_or(false, |m| m.archived);
+						match srv.threads.iter().position(|t| t.id == thread.id) {
+							Some(index) if archived => {
+								srv.threads.remove(index);
+							}
+							Some(index) => {
+								// updates omit the current user's membership
+								let member = srv.threads[index].member.take();
+								srv.threads[index].clone_from(thread);
+								if thread.member.is_none() {
+									srv.threads[index].member = member;
+								}
+							}
+							None if archived => {}
+							None => srv.threads.push(thread.clone()),
+						}
+					});
+			}
+			Event::ThreadDelete { id, server_id, .. } => {
+				self.servers
+					.iter_mut()
+					.find(|s| s.id == server_id)
+					.map(|srv| srv.threads.retain(|t| t.id != id));
+			}
+			Event::ThreadListSync {
+				server_id,
+				ref channel_ids,
+				ref threads,
+				ref members,
+			} => {
+				self.servers
+					.iter_mut()
+					.find(|s| s.id == server_id)
+					.map(|srv| {
+						match *channel_ids {
+							Some(ref ids) => srv.threads.retain(|t| match t.parent_id {
+								Some(parent) => !ids.contains(&parent) && !threads.iter().any(|n| n.id == t.id),
+								None => true,
+							}),
+							None => srv.threads.clear(),
+						}
+						for thread in threads {
+							let mut thread = thread.clone();
+							thread.member = members.iter().find(|m| m.thread_id == Some(thread.id)).cloned();
+							srv.threads.push(thread);
+						}
+					});
+			}
+			Event::ThreadMemberUpdate(server_id, ref member) => {
+				self.servers
+					.iter_mut()
+					.find(|s| s.id == server_id)
+					.and_then(|srv| srv.threads.iter_mut().find(|t| Some(t.id) == member.thread_id))
+					.map(|thread| thread.member = Some(member.clone()));
+			}
+			Event::ThreadMembersUpdate {
+				id,
+				server_id,
+				member_count,
+				ref added_members,
+				ref removed_member_ids,
+			} => {
+				let user_id = self.user.id;
+				self.servers
+					.iter_mut()
+					.find(|s| s.id == server_id)
+					.and_then(|srv| srv.threads.iter_mut().find(|t| t.id == id))
+					.map(|thread| {
+						thread.member_count = Some(member_count);
+						if let Some(member) = added_members.iter().find(|m| m.user_id == Some(user_id)) {
+							thread.member = Some(member.clone());
+						}
+						if removed_member_ids.contains(&user_id) {
+							thread.member = None;
+						}
+					});
+			}
 			Event::ChannelPinsUpdate {
 				ref channel_id,
 				ref last_pin_timestamp,
 			} => {
 				for server in &mut self.servers {
-					for channel in &mut server.channels {
+					for channel in server.channels.iter_mut().chain(&mut server.threads) {
 						if channel.id == *channel_id {
 							channel.last_pin_timestamp = *last_pin_timestamp;
 							return;
@@ -645,7 +793,7 @@ impl State {
 	/// Look up a private or public channel by its ID.
 	pub fn find_channel(&self, id: ChannelId) -> Option<ChannelRef> {
 		for server in &self.servers {
-			for channel in &server.channels {
+			for channel in server.channels.iter().chain(&server.threads) {
 				if channel.id == id {
 					return Some(ChannelRef::Public(server, channel));
 				}
@@ -0,0 +1,92 @@
+//! Local stand-ins for Discord and for proxies, shared by the unit tests.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+
+use {Discord, DiscordBuilder};
+
+/// A message with ID 2 in channel 3.
+pub const MESSAGE: &'static str = r#"{"id":"2","channel_id":"3","content":"hi","tts":false,"timestamp":"2021-01-01T00:00:00+00:00","edited_timestamp":null,"pinned":false,"type":0,"author":{"id":"1","username":"bot","discriminator":"0001","avatar":null},"mention_everyone":false,"mentions":[],"mention_roles":[],"attachments":[],"embeds":[],"flags":0}"#;
+
+/// Serve a single canned JSON response on localhost, returning the API base
+/// and a channel on which the raw request is reported.
+pub fn mock_server(status: &'static str, body: &str) -> (String, mpsc::Receiver<String>) {
+	mock_responses(vec![(status, body.to_owned())])
+}
+
+/// Serve each canned response in turn, reporting each raw request.
+pub fn mock_responses(responses: Vec<(&'static str, String)>) -> (String, mpsc::Receiver<String>) {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let addr = listener.local_addr().unwrap();
+	let (tx, rx) = mpsc::channel();
+	thread::spawn(move || {
+		for (status, body) in responses {
+			let (stream, _) = listener.accept().unwrap();
+			let mut reader = BufReader::new(stream);
+			let request = read_request(&mut reader);
+			let _ = write!(
+				reader.get_mut(),
+				"HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+				status,
+				body.len(),
+				body
+			);
+			let _ = tx.send(request);
+		}
+	});
+	(format!("http://{}/api", addr), rx)
+}
+
+fn read_request(reader: &mut BufReader<TcpStream>) -> String {
+	let mut request = String::new();
+	let mut length = 0;
+	loop {
+		let mut line = String::new();
+		reader.read_line(&mut line).unwrap();
+		if line.to_lowercase().starts_with("content-length:") {
+			length = line[15..].trim().parse().unwrap();
+		}
+		request.push_str(&line);
+		if line == "\r\n" || line.is_empty() {
+			break;
+		}
+	}
+	let mut content = vec![0; length];
+	reader.read_exact(&mut content).unwrap();
+	request.push_str(&String::from_utf8_lossy(&content));
+	request
+}
+
+/// A client for the mock server at the given API base.
+pub fn mock_discord(api_base: &str) -> Discord {
+	DiscordBuilder::new()
+		.with_api_base(api_base)
+		.with_transport(::hyper::net::HttpConnector)
+		.from_bot_token("token")
+		.unwrap()
+}
+
+/// Run an HTTP proxy which relays each `CONNECT` tunnel, returning its
+/// address and a channel on which the head of each `CONNECT` is reported.
+pub fn relay_proxy() -> (String, mpsc::Receiver<String>) {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let addr = listener.local_addr().unwrap().to_string();
+	let (tx, rx) = mpsc::channel();
+	thread::spawn(move || {
+		for client in listener.incoming() {
+			let mut reader = BufReader::new(client.unwrap());
+			let head = read_request(&mut reader);
+			let target = head.split_whitespace().nth(1).unwrap().to_owned();
+			let mut client = reader.into_inner();
+			let server = TcpStream::connect(&target[..]).unwrap();
+			client.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").unwrap();
+			let _ = tx.send(head);
+			let (mut upstream, mut downstream) = (server.try_clone().unwrap(), client.try_clone().unwrap());
+			thread::spawn(move || io::copy(&mut downstream, &mut upstream));
+			thread::spawn(move || io::copy(&mut { server }, &mut { client }));
+		}
+	});
+	(addr, rx)
+}
@@ -0,0 +1,96 @@
+//! Pluggable HTTP transport for the REST API.
+
+use std::io::{self, Read, Write};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper;
+use hyper::net::{NetworkConnector, NetworkStream};
+
+use {Error, Result};
+
+/// A source of connections over which REST requests are made.
+///
+/// Any `hyper` `NetworkConnector` can be used as a transport. For example,
+/// `hyper::net::HttpConnector` can be used to talk to a plain HTTP server
+/// when testing against a local mock of the Discord API.
+pub trait Transport: Send + Sync {
+	/// Open a connection to the given host and port using the given scheme.
+	fn connect(
+		&self,
+		host: &str,
+		port: u16,
+		scheme: &str,
+	) -> hyper::Result<Box<dyn NetworkStream + Send>>;
+}
+
+impl<C: NetworkConnector + Send + Sync> Transport for C {
+	fn connect(
+		&self,
+		host: &str,
+		port: u16,
+		scheme: &str,
+	) -> hyper::Result<Box<dyn NetworkStream + Send>> {
+		NetworkConnector::connect(self, host, port, scheme).map(Into::into)
+	}
+}
+
+/// The transport used when none is specified, supporting HTTP and HTTPS.
+pub fn default_transport() -> Result<Arc<dyn Transport>> {
+	let tls = ::hyper_native_tls::NativeTlsClient::new()
+		.map_err(|_| Error::Other("Error initializing NativeTlsClient"))?;
+	Ok(Arc::new(hyper::net::HttpsConnector::new(tls)))
+}
+
+/// Adapter allowing a `Transport` trait object to be handed to `hyper`.
+#[derive(Clone)]
+pub struct Connector(pub Arc<dyn Transport>);
+
+impl NetworkConnector for Connector {
+	type Stream = Stream;
+
+	fn connect(&self, host: &str, port: u16, scheme: &str) -> hyper::Result<Stream> {
+		self.0.connect(host, port, scheme).map(Stream)
+	}
+}
+
+/// A connection opened by a `Transport`.
+pub struct Stream(Box<dyn NetworkStream + Send>);
+
+impl Read for Stream {
+	#[inline]
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		self.0.read(buf)
+	}
+}
+
+impl Write for Stream {
+	#[inline]
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.0.write(buf)
+	}
+
+	#[inline]
+	fn flush(&mut self) -> io::Result<()> {
+		self.0.flush()
+	}
+}
+
+impl NetworkStream for Stream {
+	fn peer_addr(&mut self) -> io::Result<SocketAddr> {
+		self.0.peer_addr()
+	}
+
+	fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+		self.0.set_read_timeout(dur)
+	}
+
+	fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+		self.0.set_write_timeout(dur)
+	}
+
+	fn close(&mut self, how: ::std::net::Shutdown) -> io::Result<()> {
+		self.0.close(how)
+	}
+}
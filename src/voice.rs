@@ -1,7 +1,8 @@
 //! Voice communication module.
 //!
-//! A `VoiceConnection` for a server is obtained from a `Connection`. It can then be used to
-//! join a channel, change mute/deaf status, and play and receive audio.
+//! A `VoiceConnection` for a server is obtained from a `Connection`, or from an `EventStream` with
+//! the `async` feature. It can then be used to join a channel, change mute/deaf status, and play
+//! and receive audio.
 
 use std::collections::HashMap;
 use std::io::{self, Read, Write};
@@ -12,18 +13,18 @@ use byteorder::{BigEndian, LittleEndian, ReadBytesExt, WriteBytesExt};
 use opus;
 use serde_json;
 use sodiumoxide::crypto::secretbox as crypto;
-use websocket::client::{Client, Sender};
-use websocket::stream::WebSocketStream;
+use websocket::client::Sender;
 
 use model::*;
-use {Error, ReceiverExt, Result, SenderExt};
+use proxy::{self, WsStream};
+use {Error, Proxy, ReceiverExt, Result, SenderExt};
 
 /// An active or inactive voice connection, obtained from `Connection::voice`.
 pub struct VoiceConnection {
 	// primary WS send control
 	server_id: Option<ServerId>, // None for group and private calls
 	user_id: UserId,
-	main_ws: mpsc::Sender<::internal::Status>,
+	main_ws: Box<dyn Fn(serde_json::Value) + Send>,
 	channel_id: Option<ChannelId>,
 	mute: bool,
 	deaf: bool,
@@ -31,6 +32,7 @@ pub struct VoiceConnection {
 	// main WS receive control
 	session_id: Option<String>,
 	endpoint_token: Option<(String, String)>,
+	proxy: Option<Proxy>,
 
 	// voice thread (voice WS + UDP) control
 	sender: mpsc::Sender<Status>,
@@ -90,6 +92,24 @@ impl VoiceConnection {
 		server_id: Option<ServerId>,
 		user_id: UserId,
 		main_ws: mpsc::Sender<::internal::Status>,
+		proxy: Option<Proxy>,
+	) -> Self {
+		VoiceConnection::with_main_ws(
+			server_id,
+			user_id,
+			Box::new(move |message| {
+				let _ = main_ws.send(::internal::Status::SendMessage(message));
+			}),
+			proxy,
+		)
+	}
+
+	/// Create a voice connection which sends its gateway commands through `main_ws`.
+	pub(crate) fn with_main_ws(
+		server_id: Option<ServerId>,
+		user_id: UserId,
+		main_ws: Box<dyn Fn(serde_json::Value) + Send>,
+		proxy: Option<Proxy>,
 	) -> Self {
 		let (tx, rx) = mpsc::channel();
 		start_voice_thread(server_id, rx);
@@ -102,6 +122,7 @@ impl VoiceConnection {
 			deaf: false,
 			session_id: None,
 			endpoint_token: None,
+			proxy: proxy,
 			sender: tx,
 		}
 	}
@@ -150,7 +171,7 @@ impl VoiceConnection {
 
 	/// Send the connect/disconnect command over the main websocket
 	fn send_connect(&self) {
-		let _ = self.main_ws.send(::internal::Status::SendMessage(json! {{
+		(self.main_ws)(json! {{
 			"op": 4,
 			"d": {
 				"guild_id": self.server_id,
@@ -158,7 +179,7 @@ impl VoiceConnection {
 				"self_mute": self.mute,
 				"self_deaf": self.deaf,
 			}
-		}}));
+		}});
 	}
 
 	#[doc(hidden)]
@@ -253,6 +274,7 @@ impl VoiceConnection {
 			session_id: session_id,
 			endpoint: endpoint,
 			token: token,
+			proxy: self.proxy.clone(),
 		}));
 	}
 }
@@ -470,10 +492,11 @@ struct ConnStartInfo {
 	endpoint: String,
 	session_id: String,
 	token: String,
+	proxy: Option<Proxy>,
 }
 
 struct InternalConnection {
-	sender: Sender<WebSocketStream>,
+	sender: Sender<WsStream>,
 	receive_chan: mpsc::Receiver<RecvStatus>,
 	ws_close: mpsc::Sender<()>,
 	udp_close: mpsc::Sender<()>,
@@ -505,6 +528,7 @@ impl InternalConnection {
 			mut endpoint,
 			session_id,
 			token,
+			proxy,
 		} = info;
 
 		// prepare the URL: drop the :80 and prepend wss://
@@ -518,9 +542,7 @@ impl InternalConnection {
 			Ok(url) => url,
 			Err(_) => return Err(Error::Other("Invalid endpoint URL")),
 		};
-		let response = Client::connect(url)?.send()?;
-		response.validate()?;
-		let (mut sender, mut receiver) = response.begin().split();
+		let (mut sender, mut receiver) = proxy::connect_websocket(url, proxy.as_ref())?;
 
 		// send the handshake
 		let map = json! {{
@@ -646,17 +668,7 @@ impl InternalConnection {
 			let ws_thread = Some(try!(::std::thread::Builder::new()
 				.name(format!("{} (WS reader)", thread_name))
 				.spawn(move || {
-					{
-						match *receiver.get_mut().get_mut() {
-							WebSocketStream::Tcp(ref inner) => inner.set_nonblocking(true).unwrap(),
-							WebSocketStream::Ssl(ref inner) => inner
-								.lock()
-								.unwrap()
-								.get_ref()
-								.set_nonblocking(true)
-								.unwrap(),
-						};
-					}
+					receiver.get_mut().get_mut().set_nonblocking(true).unwrap();
 					loop {
 						while let Ok(msg) = receiver.recv_json(VoiceEvent::decode) {
 							match tx1.send(RecvStatus::Websocket(msg)) {
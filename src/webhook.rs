@@ -0,0 +1,181 @@
+//! Client for webhooks authenticated only by their token.
+
+use hyper;
+use serde_json;
+
+use builders::*;
+use model::*;
+use ratelimit::RateLimits;
+use {check_empty, from_reader, message_body, rate_limited};
+use {DiscordBuilder, Error, FileUpload, Result};
+
+/// Client for a single webhook, authenticated by the webhook's token.
+///
+/// No bot or user token is needed to execute a webhook, making this suitable
+/// for posting alerts and other notifications. Obtain one from a webhook's ID
+/// and token using `new`, from its URL using `from_url`, or from a
+/// `DiscordBuilder` using `webhook_client`.
+///
+/// ```ignore
+/// let webhook = WebhookClient::from_url("https://discord.com/api/webhooks/1234/abcd")?;
+/// webhook.execute(false, |m| m
+///     .content("Disk usage is above 90%")
+///     .username("Alerts")
+///     .embed(|e| e.title("db-01").color(0xff0000))
+/// )?;
+/// ```
+pub struct WebhookClient {
+	rate_limits: RateLimits,
+	client: hyper::Client,
+	config: DiscordBuilder,
+	id: WebhookId,
+	token: String,
+}
+
+impl WebhookClient {
+	/// Create a client for the webhook with the given ID and token.
+	pub fn new(webhook: WebhookId, token: &str) -> Result<WebhookClient> {
+		DiscordBuilder::new().webhook_client(webhook, token)
+	}
+
+	/// Create a client from a webhook URL of the form
+	/// `https://discord.com/api/webhooks/ID/TOKEN`.
+	pub fn from_url(url: &str) -> Result<WebhookClient> {
+		let (id, token) = parse_webhook_url(url)?;
+		WebhookClient::new(id, token)
+	}
+
+	pub(crate) fn from_config(config: &DiscordBuilder, id: WebhookId, token: &str) -> Result<WebhookClient> {
+		Ok(WebhookClient {
+			rate_limits: RateLimits::default(),
+			client: config.client()?,
+			config: config.clone(),
+			id: id,
+			token: token.to_owned(),
+		})
+	}
+
+	/// Get the ID of this webhook.
+	pub fn id(&self) -> WebhookId {
+		self.id
+	}
+
+	fn request<'a, F: Fn() -> hyper::client::RequestBuilder<'a>>(
+		&self,
+		method: &str,
+		url: &str,
+		f: F,
+	) -> Result<hyper::client::Response> {
+		rate_limited(&self.rate_limits, &self.config.retry_policy, method, url, || {
+			f().header(hyper::header::ContentType::json())
+		})
+	}
+
+	/// Get information about this webhook. The returned `Webhook` has no `user`.
+	pub fn get(&self) -> Result<Webhook> {
+		let response = request!(self, get, "/webhooks/{}/{}", self.id, self.token);
+		from_reader(response)
+	}
+
+	/// Edit this webhook's default name or avatar. See `EditWebhook` for the
+	/// editable fields; the channel cannot be changed using the token alone.
+	pub fn edit<F: FnOnce(EditWebhook) -> EditWebhook>(&self, f: F) -> Result<Webhook> {
+		let map = EditWebhook::__build(f);
+		let body = serde_json::to_string(&map)?;
+		let response = request!(self, patch(body), "/webhooks/{}/{}", self.id, self.token);
+		from_reader(response)
+	}
+
+	/// Delete this webhook.
+	pub fn delete(&self) -> Result<()> {
+		check_empty(request!(self, delete, "/webhooks/{}/{}", self.id, self.token))
+	}
+
+	/// Post a message through this webhook. See `ExecuteWebhook` for the
+	/// available fields.
+	///
+	/// If `wait` is true, Discord confirms that the message was saved and
+	/// returns it. Otherwise `None` is returned.
+	pub fn execute<F: FnOnce(ExecuteWebhook) -> ExecuteWebhook>(
+		&self,
+		wait: bool,
+		f: F,
+	) -> Result<Option<Message>> {
+		self.execute_with_files(wait, Vec::new(), f)
+	}
+
+	/// Post a message with files attached through this webhook.
+	///
+	/// The message may also contain content and embeds, and is sent as plain
+	/// JSON if there are no files.
+	pub fn execute_with_files<F: FnOnce(ExecuteWebhook) -> ExecuteWebhook>(
+		&self,
+		wait: bool,
+		files: Vec<FileUpload>,
+		f: F,
+	) -> Result<Option<Message>> {
+		let map = ExecuteWebhook::__build(f);
+		let (content_type, body) = message_body(&map, files)?;
+		let url = format!(
+			"{}/webhooks/{}/{}?wait={}",
+			self.config.api_base, self.id, self.token, wait
+		);
+		let response = rate_limited(&self.rate_limits, &self.config.retry_policy, "post", &url, || {
+			self.client
+				.post(&url)
+				.header(content_type.clone())
+				.body(&body[..])
+		})?;
+		if wait {
+			from_reader(response).map(Some)
+		} else {
+			check_empty(response).map(|()| None)
+		}
+	}
+}
+
+fn parse_webhook_url(url: &str) -> Result<(WebhookId, &str)> {
+	let rest = match url.find("/webhooks/") {
+		Some(index) => &url[index + "/webhooks/".len()..],
+		None => return Err(Error::Other("Not a webhook URL")),
+	};
+	let mut parts = rest.trim_end_matches('/').splitn(2, '/');
+	let id = parts.next().and_then(|id| id.parse::<u64>().ok());
+	match (id, parts.next()) {
+		(Some(id), Some(token)) if !token.is_empty() => Ok((WebhookId(id), token)),
+		_ => Err(Error::Other("Webhook URL missing ID or token")),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use test_support::{mock_server, MESSAGE};
+
+	#[test]
+	fn execute_with_files() {
+		let (base, requests) = mock_server("200 OK", MESSAGE);
+		let webhook = DiscordBuilder::new()
+			.with_api_base(&base)
+			.with_transport(hyper::net::HttpConnector)
+			.webhook_client(WebhookId(5), "secret")
+			.unwrap();
+		let file = FileUpload::new("log.txt", &b"contents"[..]);
+		let message = webhook
+			.execute_with_files(true, vec![file], |m| m.content("hi").username("Alerts"))
+			.unwrap();
+		assert_eq!(message.map(|m| m.id), Some(MessageId(2)));
+		let request = requests.recv().unwrap();
+		assert!(request.starts_with("POST /api/webhooks/5/secret?wait=true HTTP/1.1\r\n"));
+		assert!(!request.contains("Authorization"));
+		assert!(request.contains("name=\"files[0]\"; filename=\"log.txt\""));
+		assert!(request.contains(r#"{"attachments":[{"filename":"log.txt","id":0}],"content":"hi","username":"Alerts"}"#));
+	}
+
+	#[test]
+	fn from_url() {
+		let webhook = WebhookClient::from_url("https://discord.com/api/webhooks/1234/abcd").unwrap();
+		assert_eq!(webhook.id(), WebhookId(1234));
+		assert!(WebhookClient::from_url("https://discord.com/api/webhooks/1234").is_err());
+	}
+}